@@ -0,0 +1,376 @@
+//! A frozen, binary-searchable snapshot of a sorted map.
+
+use std::iter::Peekable;
+use std::ops::{Bound, Range, RangeBounds};
+
+/// Immutable sorted map storing all keys back to back in one blob, located through a
+/// `u32` offset table and found by binary search.
+///
+/// Costs 4 bytes of offset plus the raw key bytes per entry, with no tree structure at
+/// all, in exchange for O(log n) key comparisons per lookup and no updates. Suited to
+/// read-mostly data of moderate size; build it from [`HotTree::iter`](crate::HotTree::iter)
+/// or any other sorted source.
+///
+/// ```
+/// use hot_rs::SortedBlob;
+///
+/// let blob = SortedBlob::from_sorted_iter([("apple", 1), ("banana", 2), ("cherry", 3)]);
+/// assert_eq!(blob.get("banana"), Some(&2));
+/// let tail: Vec<_> = blob.range(&b"b"[..]..).map(|(_, v)| *v).collect();
+/// assert_eq!(tail, [2, 3]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SortedBlob<V> {
+    keys: Vec<u8>,
+    /// `offsets[i]..offsets[i + 1]` is key `i` in `keys`.
+    offsets: Vec<u32>,
+    values: Vec<V>,
+}
+
+/// Memory breakdown of a [`SortedBlob`], from [`SortedBlob::memory_stats`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortedBlobStats {
+    pub len: usize,
+    pub key_bytes: usize,
+    pub offset_bytes: usize,
+    pub value_bytes: usize,
+    pub total_bytes: usize,
+}
+
+impl<V> SortedBlob<V> {
+    /// Build from entries in strictly ascending key order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not strictly ascending, or if they total more than
+    /// `u32::MAX` bytes.
+    pub fn from_sorted_iter<K: AsRef<[u8]>>(entries: impl IntoIterator<Item = (K, V)>) -> Self {
+        let mut keys = Vec::new();
+        let mut offsets = vec![0u32];
+        let mut values = Vec::new();
+        let mut prev = 0..0;
+        for (key, value) in entries {
+            let key = key.as_ref();
+            assert!(
+                values.is_empty() || &keys[prev.clone()] < key,
+                "SortedBlob::from_sorted_iter: keys must be strictly ascending"
+            );
+            prev = keys.len()..keys.len() + key.len();
+            keys.extend_from_slice(key);
+            let end = u32::try_from(keys.len()).expect("SortedBlob key bytes exceed u32::MAX");
+            offsets.push(end);
+            values.push(value);
+        }
+        keys.shrink_to_fit();
+        offsets.shrink_to_fit();
+        values.shrink_to_fit();
+        Self {
+            keys,
+            offsets,
+            values,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    #[inline]
+    fn key(&self, i: usize) -> &[u8] {
+        &self.keys[self.offsets[i] as usize..self.offsets[i + 1] as usize]
+    }
+
+    /// Index of the first key `>= key` (`Ok` if equal).
+    fn search(&self, key: &[u8]) -> Result<usize, usize> {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.key(mid).cmp(key) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(lo)
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        self.search(key.as_ref()).ok().map(|i| &self.values[i])
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.search(key.as_ref()).is_ok()
+    }
+
+    /// Iterate in ascending key order.
+    pub fn iter(&self) -> SortedBlobIter<'_, V> {
+        SortedBlobIter {
+            blob: self,
+            range: 0..self.len(),
+        }
+    }
+
+    /// Iterate over the entries whose keys fall in `range`, in ascending key order.
+    pub fn range<'k>(&self, range: impl RangeBounds<&'k [u8]>) -> SortedBlobIter<'_, V> {
+        let start = match range.start_bound() {
+            Bound::Included(k) => self.search(k).unwrap_or_else(|i| i),
+            Bound::Excluded(k) => self.search(k).map_or_else(|i| i, |i| i + 1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(k) => self.search(k).map_or_else(|i| i, |i| i + 1),
+            Bound::Excluded(k) => self.search(k).unwrap_or_else(|i| i),
+            Bound::Unbounded => self.len(),
+        };
+        SortedBlobIter {
+            blob: self,
+            range: start..end.max(start),
+        }
+    }
+
+    pub fn memory_stats(&self) -> SortedBlobStats {
+        let key_bytes = self.keys.capacity();
+        let offset_bytes = self.offsets.capacity() * std::mem::size_of::<u32>();
+        let value_bytes = self.values.capacity() * std::mem::size_of::<V>();
+        SortedBlobStats {
+            len: self.len(),
+            key_bytes,
+            offset_bytes,
+            value_bytes,
+            total_bytes: key_bytes + offset_bytes + value_bytes,
+        }
+    }
+}
+
+/// Iterator over `(key, value)` in a [`SortedBlob`], borrowing keys from the blob.
+pub struct SortedBlobIter<'a, V> {
+    blob: &'a SortedBlob<V>,
+    range: Range<usize>,
+}
+
+impl<'a, V> Iterator for SortedBlobIter<'a, V> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.range.next()?;
+        Some((self.blob.key(i), &self.blob.values[i]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for SortedBlobIter<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let i = self.range.next_back()?;
+        Some((self.blob.key(i), &self.blob.values[i]))
+    }
+}
+
+impl<V> ExactSizeIterator for SortedBlobIter<'_, V> {}
+
+/// Read-only union of [`SortedBlob`] layers, e.g. one snapshot per day, queried together
+/// without merging them into one blob.
+///
+/// A key in several layers takes its value from the newest, the one pushed last. Lookups
+/// probe layers newest-first, and iteration merges all layers at O(layers) per entry, so
+/// this suits a handful of layers; rebuild one blob from [`SortedBlobSet::iter`] once
+/// there are many.
+///
+/// ```
+/// use hot_rs::{SortedBlob, SortedBlobSet};
+///
+/// let mut set = SortedBlobSet::new();
+/// set.push(SortedBlob::from_sorted_iter([("a", 1), ("b", 1)]));
+/// set.push(SortedBlob::from_sorted_iter([("b", 2), ("c", 2)]));
+/// assert_eq!(set.get("b"), Some(&2));
+/// let merged: Vec<_> = set.iter().map(|(k, v)| (k, *v)).collect();
+/// assert_eq!(merged, [(&b"a"[..], 1), (b"b", 2), (b"c", 2)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SortedBlobSet<V> {
+    /// Oldest first.
+    layers: Vec<SortedBlob<V>>,
+}
+
+impl<V> SortedBlobSet<V> {
+    pub fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    /// Add `layer` as the newest, shadowing older layers' values for its keys.
+    pub fn push(&mut self, layer: SortedBlob<V>) {
+        self.layers.push(layer);
+    }
+
+    /// Layers, oldest first.
+    pub fn layers(&self) -> &[SortedBlob<V>] {
+        &self.layers
+    }
+
+    /// Value of `key` in the newest layer holding it.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        let key = key.as_ref();
+        self.layers.iter().rev().find_map(|layer| layer.get(key))
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        let key = key.as_ref();
+        self.layers.iter().any(|layer| layer.contains_key(key))
+    }
+
+    /// Iterate over the distinct keys of all layers in ascending order, each with its
+    /// newest value.
+    pub fn iter(&self) -> SortedBlobSetIter<'_, V> {
+        SortedBlobSetIter::new(self.layers.iter().map(SortedBlob::iter).collect())
+    }
+
+    /// [`SortedBlobSet::iter`] restricted to keys in `range`.
+    pub fn range<'k>(&self, range: impl RangeBounds<&'k [u8]>) -> SortedBlobSetIter<'_, V> {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        SortedBlobSetIter::new(self.layers.iter().map(|l| l.range(bounds)).collect())
+    }
+}
+
+impl<V> Default for SortedBlobSet<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Merged iterator over the layers of a [`SortedBlobSet`].
+pub struct SortedBlobSetIter<'a, V> {
+    /// One stream per layer, oldest first.
+    streams: Vec<Peekable<SortedBlobIter<'a, V>>>,
+}
+
+impl<'a, V> SortedBlobSetIter<'a, V> {
+    fn new(streams: Vec<SortedBlobIter<'a, V>>) -> Self {
+        Self {
+            streams: streams.into_iter().map(Iterator::peekable).collect(),
+        }
+    }
+}
+
+impl<'a, V> Iterator for SortedBlobSetIter<'a, V> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Smallest head key; on ties the later (newer) layer wins.
+        let mut best: Option<(&'a [u8], &'a V)> = None;
+        for stream in &mut self.streams {
+            if let Some(&(key, value)) = stream.peek() {
+                if best.is_none_or(|(best_key, _)| key <= best_key) {
+                    best = Some((key, value));
+                }
+            }
+        }
+        let (key, _) = best?;
+        for stream in &mut self.streams {
+            stream.next_if(|&(k, _)| k == key);
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HotTree;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_sorted_blob_round_trip() {
+        let mut tree: HotTree<u64> = HotTree::new();
+        let mut model = BTreeMap::new();
+        for i in 0..50_000u64 {
+            let key = format!("https://example.com/{}/{}", i % 251, i * 7).into_bytes();
+            tree.insert(&key, i);
+            model.insert(key, i);
+        }
+        let blob = SortedBlob::from_sorted_iter(tree.iter().map(|(k, v)| (k, *v)));
+        assert_eq!(blob.len(), 50_000);
+
+        for (k, v) in &model {
+            assert_eq!(blob.get(k), Some(v));
+        }
+        assert_eq!(blob.get(b"https://example.com/"), None);
+        assert_eq!(blob.get(b"zzz"), None);
+        assert!(blob
+            .iter()
+            .map(|(k, _)| k)
+            .eq(model.keys().map(Vec::as_slice)));
+
+        let lo = &b"https://example.com/17/"[..];
+        let hi = &b"https://example.com/2"[..];
+        let got: Vec<u64> = blob.range(lo..hi).map(|(_, v)| *v).collect();
+        let expected: Vec<u64> = model
+            .range(lo.to_vec()..hi.to_vec())
+            .map(|(_, v)| *v)
+            .collect();
+        assert!(!got.is_empty());
+        assert_eq!(got, expected);
+        assert_eq!(blob.range(hi..lo).count(), 0);
+        assert_eq!(
+            blob.range(..).next_back(),
+            model.iter().next_back().map(|(k, v)| (&k[..], v))
+        );
+
+        let stats = blob.memory_stats();
+        let key_bytes: usize = model.keys().map(Vec::len).sum();
+        assert_eq!(stats.key_bytes, key_bytes);
+        assert_eq!(stats.total_bytes, key_bytes + 4 * 50_001 + 8 * 50_000);
+    }
+
+    #[test]
+    fn test_sorted_blob_set_newest_wins() {
+        let day = |d: u64, keys: &[&str]| {
+            SortedBlob::from_sorted_iter(keys.iter().map(|k| (k.as_bytes(), d)))
+        };
+        let mut set = SortedBlobSet::new();
+        set.push(day(1, &["a", "c", "e", "g"]));
+        set.push(day(2, &["b", "e"]));
+        set.push(day(3, &["d", "g", "h"]));
+
+        assert_eq!(set.get("a"), Some(&1));
+        assert_eq!(set.get("e"), Some(&2));
+        assert_eq!(set.get("g"), Some(&3));
+        assert_eq!(set.get("f"), None);
+        assert!(set.contains_key("h"));
+
+        let merged: Vec<(&[u8], u64)> = set.iter().map(|(k, v)| (k, *v)).collect();
+        let expected: Vec<(&[u8], u64)> = [
+            ("a", 1),
+            ("b", 2),
+            ("c", 1),
+            ("d", 3),
+            ("e", 2),
+            ("g", 3),
+            ("h", 3),
+        ]
+        .iter()
+        .map(|&(k, v)| (k.as_bytes(), v))
+        .collect();
+        assert_eq!(merged, expected);
+
+        let mid: Vec<(&[u8], u64)> = set
+            .range(&b"c"[..]..=&b"g"[..])
+            .map(|(k, v)| (k, *v))
+            .collect();
+        assert_eq!(mid, expected[2..6]);
+        assert_eq!(SortedBlobSet::<u64>::new().iter().next(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly ascending")]
+    fn test_sorted_blob_rejects_unsorted() {
+        SortedBlob::from_sorted_iter([("b", 1), ("a", 2)]);
+    }
+}
@@ -0,0 +1,72 @@
+//! Bloom filter sidecar for rejecting absent keys before descending the trie.
+
+use std::f64::consts::LN_2;
+
+/// A Bloom filter over 64-bit key hashes, probed at `num_hashes` positions derived by
+/// double hashing.
+///
+/// Bits are only ever set, so removed keys remain (stale) positives.
+#[derive(Clone, Debug)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    /// Keys added so far, for [`BloomFilter::false_positive_rate`].
+    inserted: usize,
+}
+
+impl BloomFilter {
+    /// Size the filter so that `expected_keys` keys give a false-positive rate of about
+    /// `fp_rate`.
+    pub(crate) fn new(expected_keys: usize, fp_rate: f64) -> Self {
+        assert!(
+            fp_rate > 0.0 && fp_rate < 1.0,
+            "Bloom filter false-positive rate must be in (0, 1), got {fp_rate}"
+        );
+        let n = expected_keys.max(1) as f64;
+        let num_bits = (-n * fp_rate.ln() / (LN_2 * LN_2)).ceil().max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / n) * LN_2).round().clamp(1.0, 32.0) as u32;
+        Self {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+            inserted: 0,
+        }
+    }
+
+    #[inline]
+    fn positions(&self, hash: u64) -> impl Iterator<Item = u64> {
+        let h2 = hash.rotate_left(32) | 1;
+        let num_bits = self.num_bits;
+        (0..self.num_hashes as u64).map(move |i| hash.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    pub(crate) fn insert(&mut self, hash: u64) {
+        for pos in self.positions(hash) {
+            self.bits[(pos / 64) as usize] |= 1 << (pos % 64);
+        }
+        self.inserted += 1;
+    }
+
+    /// `false` only if a key with this hash was never inserted.
+    #[inline]
+    pub(crate) fn may_contain(&self, hash: u64) -> bool {
+        self.positions(hash)
+            .all(|pos| self.bits[(pos / 64) as usize] & (1 << (pos % 64)) != 0)
+    }
+
+    pub(crate) fn num_bits(&self) -> usize {
+        self.num_bits as usize
+    }
+
+    /// Expected false-positive rate for the keys inserted so far.
+    pub(crate) fn false_positive_rate(&self) -> f64 {
+        let k = self.num_hashes as f64;
+        let fill = 1.0 - (-k * self.inserted as f64 / self.num_bits as f64).exp();
+        fill.powf(k)
+    }
+
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.bits.capacity() * 8
+    }
+}
@@ -0,0 +1,177 @@
+//! Trees whose values are kept encoded in a side blob and decoded on access.
+
+use std::marker::PhantomData;
+
+use crate::HotTree;
+
+/// Converts values to and from bytes for [`CodecHotTree`].
+pub trait ValueCodec<V> {
+    fn encode(&self, value: &V) -> Vec<u8>;
+    fn decode(&self, bytes: &[u8]) -> V;
+}
+
+/// Location of an encoded value in the blob.
+#[derive(Clone, Copy, Debug)]
+struct BlobRef {
+    offset: u64,
+    len: u32,
+}
+
+/// A [`HotTree`] that stores each value encoded by `C` in an append-only blob, keeping only
+/// a fixed-size reference per key in the tree.
+///
+/// The trie and its value slots stay the same size whatever the values are, so large
+/// values do not push the index out of cache; the cost is a decode on every read. Bytes of
+/// replaced and removed values stay in the blob, as reported by
+/// [`CodecHotTree::dead_blob_bytes`].
+pub struct CodecHotTree<V, C> {
+    index: HotTree<BlobRef>,
+    blob: Vec<u8>,
+    dead_blob_bytes: usize,
+    codec: C,
+    _marker: PhantomData<fn(V) -> V>,
+}
+
+impl<V, C: ValueCodec<V>> CodecHotTree<V, C> {
+    pub fn new(codec: C) -> Self {
+        Self {
+            index: HotTree::new(),
+            blob: Vec::new(),
+            dead_blob_bytes: 0,
+            codec,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    fn bytes(&self, r: BlobRef) -> &[u8] {
+        &self.blob[r.offset as usize..r.offset as usize + r.len as usize]
+    }
+
+    /// Encode `value` under `key`, returning the decoded previous value.
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: &V) -> Option<V> {
+        let encoded = self.codec.encode(value);
+        let r = BlobRef {
+            offset: self.blob.len() as u64,
+            len: u32::try_from(encoded.len()).expect("encoded value exceeds u32::MAX bytes"),
+        };
+        self.blob.extend_from_slice(&encoded);
+        let old = self.index.insert(key, r)?;
+        self.dead_blob_bytes += old.len as usize;
+        Some(self.codec.decode(self.bytes(old)))
+    }
+
+    /// Decode the value for `key`.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<V> {
+        self.get_encoded(key).map(|bytes| self.codec.decode(bytes))
+    }
+
+    /// The encoded bytes of the value for `key`, without decoding.
+    pub fn get_encoded(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        self.index.get(key).map(|&r| self.bytes(r))
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<V> {
+        let old = self.index.remove(key)?;
+        self.dead_blob_bytes += old.len as usize;
+        Some(self.codec.decode(self.bytes(old)))
+    }
+
+    /// Iterate in key order, decoding each value.
+    pub fn iter(&self) -> impl Iterator<Item = (Vec<u8>, V)> + '_ {
+        self.index
+            .iter()
+            .map(|(k, &r)| (k, self.codec.decode(self.bytes(r))))
+    }
+
+    /// Memory used by the trie and its fixed-size value references.
+    pub fn index_memory_usage(&self) -> usize {
+        self.index.memory_usage()
+    }
+
+    /// Memory used by the trie plus the value blob.
+    pub fn memory_usage(&self) -> usize {
+        self.index.memory_usage() + self.blob.capacity()
+    }
+
+    /// Blob bytes of values that were replaced or removed.
+    pub fn dead_blob_bytes(&self) -> usize {
+        self.dead_blob_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Record {
+        id: u64,
+        name: String,
+    }
+
+    /// Little-endian id followed by the name's bytes.
+    struct RecordCodec;
+
+    impl ValueCodec<Record> for RecordCodec {
+        fn encode(&self, value: &Record) -> Vec<u8> {
+            let mut out = value.id.to_le_bytes().to_vec();
+            out.extend_from_slice(value.name.as_bytes());
+            out
+        }
+
+        fn decode(&self, bytes: &[u8]) -> Record {
+            let (id, name) = bytes.split_at(8);
+            Record {
+                id: u64::from_le_bytes(id.try_into().unwrap()),
+                name: String::from_utf8(name.to_vec()).unwrap(),
+            }
+        }
+    }
+
+    fn record(i: u64, name_len: usize) -> Record {
+        Record {
+            id: i,
+            name: format!("{i}").repeat(name_len),
+        }
+    }
+
+    #[test]
+    fn test_codec_tree_round_trip() {
+        let mut small = CodecHotTree::new(RecordCodec);
+        let mut large = CodecHotTree::new(RecordCodec);
+        for i in 0..2000u64 {
+            let key = format!("user/{i}");
+            assert_eq!(small.insert(&key, &record(i, 1)), None);
+            assert_eq!(large.insert(&key, &record(i, 100)), None);
+        }
+        assert_eq!(small.index_memory_usage(), large.index_memory_usage());
+        assert!(large.memory_usage() > small.memory_usage());
+
+        assert_eq!(large.insert("user/7", &record(70, 2)), Some(record(7, 100)));
+        assert_eq!(large.remove("user/8"), Some(record(8, 100)));
+        assert_eq!(large.dead_blob_bytes(), 2 * (8 + 100));
+        assert_eq!(large.get("user/7"), Some(record(70, 2)));
+        assert_eq!(large.get("user/8"), None);
+        assert_eq!(large.get_encoded("user/9").map(<[u8]>::len), Some(8 + 100));
+        assert_eq!(large.len(), 1999);
+
+        for (k, v) in small.iter() {
+            let i: u64 = std::str::from_utf8(&k[5..]).unwrap().parse().unwrap();
+            assert_eq!(v, record(i, 1));
+        }
+    }
+}
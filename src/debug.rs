@@ -0,0 +1,116 @@
+//! Views of internal node-layout decisions, for tests and fuzzers.
+
+use crate::DiscriminativeBitsRepresentation;
+
+/// The partial-key mapping a compound node would use for a set of discriminative bits,
+/// with the number of bits it extracts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappingKind {
+    /// One 64-bit mask over an 8-byte window of the key.
+    Single { num_bits: u16 },
+    /// Up to 8 gathered bytes, each with its own bit mask.
+    Multi1 { num_bits: u16 },
+    /// Up to 16 gathered bytes, for at most 16 bits.
+    Multi2 { num_bits: u16 },
+    /// Up to 32 gathered bytes.
+    Multi4 { num_bits: u16 },
+    /// Up to 8 byte groups, for bits spread over more than 4 runs of 256 bytes.
+    Multi8 { num_bits: u16 },
+}
+
+impl MappingKind {
+    pub fn num_bits(self) -> u16 {
+        match self {
+            Self::Single { num_bits }
+            | Self::Multi1 { num_bits }
+            | Self::Multi2 { num_bits }
+            | Self::Multi4 { num_bits }
+            | Self::Multi8 { num_bits } => num_bits,
+        }
+    }
+}
+
+/// The smallest mapping that can extract the given discriminative bit positions
+/// (MSB-first bit offsets into the key), as chosen when building a compound node.
+///
+/// # Panics
+///
+/// Panics if `bits` is empty, holds more than 31 distinct positions (a compound node has at
+/// most 32 entries), or cannot be represented by any mapping.
+pub fn build_mapping(bits: &[u16]) -> MappingKind {
+    let mut distinct = bits.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    assert!(
+        !distinct.is_empty() && distinct.len() < 32,
+        "build_mapping: expected 1..=31 distinct bits, got {}",
+        distinct.len()
+    );
+    let mapping = DiscriminativeBitsRepresentation::build_minimal(&distinct);
+    let num_bits = mapping.num_bits();
+    match mapping {
+        DiscriminativeBitsRepresentation::Single(_) => MappingKind::Single { num_bits },
+        DiscriminativeBitsRepresentation::Multi1(_) => MappingKind::Multi1 { num_bits },
+        DiscriminativeBitsRepresentation::Multi2(_) => MappingKind::Multi2 { num_bits },
+        DiscriminativeBitsRepresentation::Multi4(_) => MappingKind::Multi4 { num_bits },
+        DiscriminativeBitsRepresentation::Multi8(_) => MappingKind::Multi8 { num_bits },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_mapping_boundaries() {
+        // A single 8-byte window: bytes 0..=7.
+        assert_eq!(build_mapping(&[3]), MappingKind::Single { num_bits: 1 });
+        assert_eq!(build_mapping(&[0, 63]), MappingKind::Single { num_bits: 2 });
+        assert_eq!(
+            build_mapping(&[63, 0, 63]),
+            MappingKind::Single { num_bits: 2 }
+        );
+        // Byte 8 no longer fits the window, but 8 distinct bytes fit one gather.
+        assert_eq!(build_mapping(&[0, 64]), MappingKind::Multi1 { num_bits: 2 });
+        let eight_bytes: Vec<u16> = (0..8).map(|i| i * 100).collect();
+        assert_eq!(
+            build_mapping(&eight_bytes),
+            MappingKind::Multi1 { num_bits: 8 }
+        );
+        // A ninth byte needs a second group, the 16-byte layout while bits <= 16...
+        let nine_bytes: Vec<u16> = (0..9).map(|i| i * 100).collect();
+        assert_eq!(
+            build_mapping(&nine_bytes),
+            MappingKind::Multi2 { num_bits: 9 }
+        );
+        let sixteen_bits: Vec<u16> = (0..16).map(|i| i * 50).collect();
+        assert_eq!(
+            build_mapping(&sixteen_bits),
+            MappingKind::Multi2 { num_bits: 16 }
+        );
+        // ...and the 32-byte layout beyond.
+        let seventeen_bits: Vec<u16> = (0..17).map(|i| i * 50).collect();
+        assert_eq!(
+            build_mapping(&seventeen_bits),
+            MappingKind::Multi4 { num_bits: 17 }
+        );
+        // One group spans at most 256 bytes, so 5 widely spaced bytes need 5 groups.
+        let four_runs: Vec<u16> = (0..4).map(|i| i * 300 * 8).collect();
+        assert_eq!(
+            build_mapping(&four_runs),
+            MappingKind::Multi4 { num_bits: 4 }
+        );
+        let five_runs: Vec<u16> = (0..5).map(|i| i * 300 * 8).collect();
+        assert_eq!(
+            build_mapping(&five_runs),
+            MappingKind::Multi8 { num_bits: 5 }
+        );
+        assert_eq!(build_mapping(&five_runs).num_bits(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "1..=31 distinct bits")]
+    fn test_build_mapping_rejects_empty() {
+        build_mapping(&[]);
+    }
+}
@@ -0,0 +1,167 @@
+//! Varint and length-prefixed byte encodings with a stable wire format, and
+//! [`key_length_histogram`] for profiling key lengths before choosing a layout.
+//!
+//! # Format version 1
+//!
+//! This format is fixed: bytes written by any release reporting [`FORMAT_VERSION`] `1`
+//! decode the same way in every later such release, so it is safe for on-disk data.
+//!
+//! - A **varint** is an unsigned LEB128 `u64`: 7 bits per byte, least significant group
+//!   first, with the high bit set on every byte except the last. Encodings are minimal, so
+//!   each value has exactly one encoding of 1 to 10 bytes; decoders reject non-minimal
+//!   encodings and values over `u64::MAX`.
+//! - A **byte string** is its length as a varint followed by that many bytes.
+//!
+//! Any change to either layout will come with a new [`FORMAT_VERSION`].
+
+/// Version of the wire format described in the [module docs](self).
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Longest varint encoding, for a value with its top bit set.
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// Append `value` to `out` as a minimal LEB128 varint.
+pub fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Decode a varint from the start of `bytes`, returning the value and the number of bytes
+/// it used.
+///
+/// Returns `None` if `bytes` ends mid-varint, the encoding is not minimal, or the value
+/// overflows a `u64`.
+pub fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().take(MAX_VARINT_LEN).enumerate() {
+        let group = (byte & 0x7f) as u64;
+        // The tenth byte holds only the top bit of a u64.
+        if i == MAX_VARINT_LEN - 1 && byte > 1 {
+            return None;
+        }
+        value |= group << (7 * i);
+        if byte & 0x80 == 0 {
+            // A zero final group after the first byte could have been left off.
+            if i > 0 && byte == 0 {
+                return None;
+            }
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Append `bytes` to `out`, preceded by its length as a varint.
+pub fn write_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    write_varint(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+/// Decode a length-prefixed byte string from the start of `bytes`, returning it and the
+/// number of bytes it used, length included.
+///
+/// Returns `None` if the length prefix is invalid or more bytes are declared than remain.
+pub fn read_bytes(bytes: &[u8]) -> Option<(&[u8], usize)> {
+    let (len, header) = read_varint(bytes)?;
+    let len = usize::try_from(len).ok()?;
+    let body = bytes.get(header..header.checked_add(len)?)?;
+    Some((body, header + len))
+}
+
+/// Count keys by length: entry `i` is the number of keys exactly `i` bytes long.
+///
+/// Useful before building a tree, e.g. to judge whether keys are long enough for prefix
+/// compression to pay off. The result is empty when `keys` is.
+pub fn key_length_histogram<I: IntoIterator<Item = Vec<u8>>>(keys: I) -> Vec<usize> {
+    let mut counts = Vec::new();
+    for key in keys {
+        if counts.len() <= key.len() {
+            counts.resize(key.len() + 1, 0);
+        }
+        counts[key.len()] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_varint_known_encodings() {
+        let cases: [(u64, &[u8]); 6] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (300, &[0xac, 0x02]),
+            (
+                u64::MAX,
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+        ];
+        for (value, encoded) in cases {
+            let mut out = Vec::new();
+            write_varint(value, &mut out);
+            assert_eq!(out, encoded, "{value}");
+            assert_eq!(read_varint(encoded), Some((value, encoded.len())));
+        }
+
+        // Non-minimal, overflowing and unterminated encodings.
+        assert_eq!(read_varint(&[0x80, 0x00]), None);
+        let mut overflow = vec![0xff; 9];
+        overflow.push(0x02);
+        assert_eq!(read_varint(&overflow), None);
+        assert_eq!(read_varint(&[0x80; 11]), None);
+        assert_eq!(read_varint(&[]), None);
+        assert_eq!(read_bytes(&[0x05, b'a', b'b']), None);
+    }
+
+    #[test]
+    fn test_key_length_histogram() {
+        let keys = ["", "a", "bb", "cc", "dddd"].map(|k| k.as_bytes().to_vec());
+        assert_eq!(key_length_histogram(keys), vec![1, 1, 2, 0, 1]);
+        assert!(key_length_histogram(Vec::new()).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_varint_round_trips(value: u64, trailing in proptest::collection::vec(any::<u8>(), 0..4)) {
+            let mut out = Vec::new();
+            write_varint(value, &mut out);
+            prop_assert!(out.len() <= MAX_VARINT_LEN);
+            let len = out.len();
+            out.extend_from_slice(&trailing);
+            prop_assert_eq!(read_varint(&out), Some((value, len)));
+            for cut in 0..len {
+                prop_assert_eq!(read_varint(&out[..cut]), None);
+            }
+        }
+
+        #[test]
+        fn prop_bytes_round_trip(blobs in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..300), 0..8)) {
+            let mut out = Vec::new();
+            let mut starts = Vec::new();
+            for blob in &blobs {
+                starts.push(out.len());
+                write_bytes(blob, &mut out);
+            }
+            let mut pos = 0;
+            for blob in &blobs {
+                let (decoded, used) = read_bytes(&out[pos..]).expect("complete record");
+                prop_assert_eq!(decoded, blob.as_slice());
+                pos += used;
+            }
+            prop_assert_eq!(pos, out.len());
+            if let Some(&start) = starts.last() {
+                for cut in start..out.len() {
+                    prop_assert_eq!(read_bytes(&out[start..cut]), None);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Trees whose keys all have one compile-time length.
+
+use crate::{HotError, HotTree};
+
+/// A [`HotTree`] for keys of exactly `N` bytes, such as 16-byte UUIDs.
+///
+/// Leaves store the `N` key bytes bare, with no prefix id or suffix length, saving 3 bytes
+/// per key over the general layout and skipping header decoding on every key comparison.
+/// Keys with a long common prefix gain nothing from prefix compression here, so prefer
+/// [`HotTree`] unless keys are fixed-length and high-entropy.
+///
+/// ```
+/// use hot_rs::FixedKeyHot;
+///
+/// let mut ids: FixedKeyHot<4, &str> = FixedKeyHot::new();
+/// ids.insert(*b"\x00\x00\x00\x2a", "answer");
+/// assert_eq!(ids.get(b"\x00\x00\x00\x2a"), Some(&"answer"));
+/// assert!(ids.try_insert(b"short", "no").is_err());
+/// ```
+pub struct FixedKeyHot<const N: usize, V> {
+    tree: HotTree<V>,
+}
+
+impl<const N: usize, V> FixedKeyHot<N, V> {
+    pub fn new() -> Self {
+        Self {
+            tree: HotTree::new().with_fixed_key_len(N),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Insert `key`, returning the previous value.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`HotTree::try_insert`] would return an error.
+    pub fn insert(&mut self, key: [u8; N], value: V) -> Option<V> {
+        self.tree.insert(key, value)
+    }
+
+    /// Insert a key whose length is only known at runtime, returning
+    /// [`HotError::WrongKeyLength`] unless it is `N` bytes.
+    pub fn try_insert(&mut self, key: &[u8], value: V) -> Result<Option<V>, HotError> {
+        if key.len() != N {
+            return Err(HotError::WrongKeyLength {
+                len: key.len(),
+                expected: N,
+            });
+        }
+        self.tree.try_insert(key, value)
+    }
+
+    pub fn get(&self, key: &[u8; N]) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &[u8; N]) -> Option<&mut V> {
+        self.tree.get_mut(key)
+    }
+
+    pub fn contains_key(&self, key: &[u8; N]) -> bool {
+        self.tree.contains_key(key)
+    }
+
+    pub fn remove(&mut self, key: &[u8; N]) -> Option<V> {
+        self.tree.remove(key)
+    }
+
+    /// Iterate in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = ([u8; N], &V)> + '_ {
+        self.tree.iter().map(|(key, value)| {
+            let key = key
+                .try_into()
+                .expect("fixed-length tree holds only N-byte keys");
+            (key, value)
+        })
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.tree.memory_usage()
+    }
+}
+
+impl<const N: usize, V> Default for FixedKeyHot<N, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_fixed_key_tree_matches_general_tree() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(16);
+        let keys: Vec<[u8; 16]> = (0..50_000).map(|_| rng.gen()).collect();
+
+        let mut fixed: FixedKeyHot<16, u32> = FixedKeyHot::new();
+        let mut general: HotTree<u32> = HotTree::new();
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(fixed.insert(*key, i as u32), None);
+            general.insert(key, i as u32);
+        }
+        assert_eq!(fixed.len(), keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(fixed.get(key), Some(&(i as u32)));
+        }
+        assert!(fixed
+            .iter()
+            .map(|(k, v)| (k.to_vec(), *v))
+            .eq(general.iter().map(|(k, v)| (k, *v))));
+
+        // 3 fewer header bytes per leaf, at least.
+        assert!(
+            fixed.memory_usage() + 3 * keys.len() <= general.memory_usage(),
+            "{} vs {}",
+            fixed.memory_usage(),
+            general.memory_usage()
+        );
+
+        assert_eq!(fixed.remove(&keys[0]), Some(0));
+        assert!(!fixed.contains_key(&keys[0]));
+        *fixed.get_mut(&keys[1]).unwrap() += 100;
+        assert_eq!(fixed.get(&keys[1]), Some(&101));
+        assert_eq!(
+            fixed.try_insert(&keys[2][..15], 0),
+            Err(HotError::WrongKeyLength {
+                len: 15,
+                expected: 16
+            })
+        );
+        assert_eq!(fixed.try_insert(&keys[2], 7), Ok(Some(2)));
+    }
+}
@@ -0,0 +1,120 @@
+//! Read-only trees for sharing across threads.
+
+use std::hash::BuildHasher;
+use std::ops::RangeBounds;
+
+use crate::{FnvBuildHasher, HotTree, Iter};
+
+/// A [`HotTree`] that can no longer be modified, from [`HotTree::into_frozen`].
+///
+/// Reads never mutate a tree, so a `HotTree` is already `Sync` when `V` is; this wrapper
+/// makes the read-only intent part of the type. Share it as `Arc<FrozenHotTree<V>>` to
+/// let any number of threads read without a lock.
+///
+/// ```
+/// use std::sync::Arc;
+/// use hot_rs::HotTree;
+///
+/// let mut tree: HotTree<u32> = HotTree::new();
+/// tree.insert("a", 1);
+/// let frozen = Arc::new(tree.into_frozen());
+/// let reader = Arc::clone(&frozen);
+/// let got = std::thread::spawn(move || reader.get("a").copied()).join().unwrap();
+/// assert_eq!(got, Some(1));
+/// ```
+pub struct FrozenHotTree<V, S = FnvBuildHasher> {
+    tree: HotTree<V, S>,
+}
+
+impl<V, S: BuildHasher> FrozenHotTree<V, S> {
+    pub(crate) fn new(tree: HotTree<V, S>) -> Self {
+        Self { tree }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.tree.contains_key(key)
+    }
+
+    /// Iterate in ascending key order.
+    pub fn iter(&self) -> Iter<'_, V, S> {
+        self.tree.iter()
+    }
+
+    /// See [`HotTree::range`].
+    pub fn range<'k>(
+        &self,
+        range: impl RangeBounds<&'k [u8]>,
+    ) -> impl Iterator<Item = (Vec<u8>, &V)> + '_ {
+        self.tree.range(range)
+    }
+
+    /// See [`HotTree::prefix_scan`].
+    pub fn prefix_scan(&self, prefix: &[u8]) -> impl Iterator<Item = (Vec<u8>, &V)> + '_ {
+        self.tree.prefix_scan(prefix)
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.tree.memory_usage()
+    }
+
+    /// Make the tree mutable again.
+    pub fn into_inner(self) -> HotTree<V, S> {
+        self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_frozen_tree_concurrent_readers() {
+        let mut tree: HotTree<u64> = HotTree::new();
+        for i in 0..20_000u64 {
+            tree.insert(format!("https://site{}.example/{i}", i % 16), i);
+        }
+        let before = tree.memory_usage();
+        let frozen = Arc::new(tree.into_frozen());
+        assert!(frozen.memory_usage() <= before);
+
+        let readers: Vec<_> = (0..8u64)
+            .map(|t| {
+                let frozen = Arc::clone(&frozen);
+                std::thread::spawn(move || {
+                    for i in (t..20_000).step_by(8) {
+                        let key = format!("https://site{}.example/{i}", i % 16);
+                        assert_eq!(frozen.get(&key), Some(&i));
+                    }
+                    let prefix = format!("https://site{t}.example/");
+                    let scanned = frozen.prefix_scan(prefix.as_bytes()).count();
+                    assert_eq!(scanned, 20_000 / 16);
+                    frozen.iter().count()
+                })
+            })
+            .collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), 20_000);
+        }
+        assert_eq!(
+            frozen
+                .range(&b"https://site1."[..]..&b"https://site2"[..])
+                .count(),
+            20_000 / 16 * 7
+        );
+    }
+}
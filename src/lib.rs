@@ -20,8 +20,39 @@
 
 #![deny(unsafe_op_in_unsafe_fn)]
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
 use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use bloom::BloomFilter;
+use sketch::TailSketch;
+
+mod blob;
+mod bloom;
+mod codec;
+pub mod debug;
+pub mod encoding;
+mod fixed;
+mod frozen;
+mod map;
+mod merge;
+mod prefix_pool;
+mod set;
+mod sketch;
+mod wal;
+
+pub use blob::{SortedBlob, SortedBlobIter, SortedBlobSet, SortedBlobSetIter, SortedBlobStats};
+pub use codec::{CodecHotTree, ValueCodec};
+pub use fixed::FixedKeyHot;
+pub use frozen::FrozenHotTree;
+pub use map::{load_tsv, ByteMap, TransformedKV};
+pub use merge::{merge_join, EitherOrBoth, MergeJoin};
+pub use prefix_pool::SharedPrefixPool;
+pub use set::HotSet;
+pub use wal::WalHotTree;
 
 #[derive(Clone, Copy)]
 struct InsertFrame {
@@ -32,6 +63,68 @@ struct InsertFrame {
     msb: u16,
 }
 
+/// Parsed leaf header: `[prefix_id:2][tail_id:2]?[suffix_len:1-3]`, followed by the
+/// inline suffix bytes. `tail_id` is present only with suffix interning and is 0 otherwise.
+/// Fixed-length leaves have no header; the record starts with the whole key.
+#[derive(Clone, Copy)]
+struct LeafRecord {
+    prefix_id: u16,
+    tail_id: u16,
+    suffix_start: usize,
+    suffix_len: usize,
+}
+
+/// A stored key as `prefix ++ mid ++ tail`, where `mid` is inline in the leaf and
+/// `tail` is an interned suffix (empty unless suffix interning is enabled).
+#[derive(Clone, Copy)]
+struct LeafKey<'a> {
+    prefix: &'a [u8],
+    mid: &'a [u8],
+    tail: &'a [u8],
+}
+
+impl LeafKey<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.prefix.len() + self.mid.len() + self.tail.len()
+    }
+
+    /// Byte `i` of the key's bit string; see [`key_byte`].
+    #[inline]
+    fn byte(&self, i: usize) -> u8 {
+        if i < self.prefix.len() {
+            return self.prefix[i];
+        }
+        let j = i - self.prefix.len();
+        if j < self.mid.len() {
+            return self.mid[j];
+        }
+        match self.tail.get(j - self.mid.len()) {
+            Some(&b) => b,
+            None => pad_byte(self.len(), i),
+        }
+    }
+
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        prefix.len() <= self.len() && prefix.iter().enumerate().all(|(i, &b)| self.byte(i) == b)
+    }
+
+    #[inline]
+    fn equals(&self, key: &[u8]) -> bool {
+        key.len() == self.len()
+            && key.starts_with(self.prefix)
+            && key.ends_with(self.tail)
+            && &key[self.prefix.len()..key.len() - self.tail.len()] == self.mid
+    }
+
+    fn extend_into(&self, out: &mut Vec<u8>) {
+        out.reserve(self.len());
+        out.extend_from_slice(self.prefix);
+        out.extend_from_slice(self.mid);
+        out.extend_from_slice(self.tail);
+    }
+}
+
 #[derive(Clone, Copy)]
 struct BiNodeSplit {
     disc: u16,
@@ -47,6 +140,319 @@ struct BiNodeSplit {
 const MIN_PREFIX_LEN: usize = 4; // Minimum prefix length to consider
 const MAX_PREFIX_LEN: usize = 128; // Maximum prefix length
 const MAX_PREFIXES: usize = 65535; // Maximum unique prefixes (u16 max - 1)
+/// Longest key whose bit positions fit the 16-bit discriminative bit index, less the two
+/// bytes that hold the key length.
+pub const MAX_KEY_LEN: usize = (u16::MAX as usize + 1) / 8 - 2;
+
+/// Byte `i` of the bit string that discriminative bits index: the key, zero padding, and
+/// the key length as a big-endian `u16` in the last two bytes of the bit space.
+///
+/// The length lets keys that differ only by trailing `0x00` bytes (`b""` and `b"\x00"`)
+/// branch on a bit past every real key byte, ordering the shorter key first as byte
+/// strings do.
+#[inline]
+fn key_byte(key: &[u8], i: usize) -> u8 {
+    match key.get(i) {
+        Some(&b) => b,
+        None => pad_byte(key.len(), i),
+    }
+}
+
+/// Byte `i` past the end of a `len`-byte key; see [`key_byte`].
+#[inline]
+fn pad_byte(len: usize, i: usize) -> u8 {
+    match i.checked_sub(MAX_KEY_LEN) {
+        Some(j) if j < 2 => (len as u16).to_be_bytes()[j],
+        _ => 0,
+    }
+}
+
+/// First bit at which the bit strings of `len_a`- and `len_b`-byte keys read through
+/// `byte_a` and `byte_b` differ, or `None` if the keys are equal.
+#[inline]
+fn first_diff_bit_by(
+    len_a: usize,
+    len_b: usize,
+    byte_a: impl Fn(usize) -> u8,
+    byte_b: impl Fn(usize) -> u8,
+) -> Option<u16> {
+    (0..len_a.max(len_b))
+        .chain(MAX_KEY_LEN..MAX_KEY_LEN + 2)
+        .find_map(|i| {
+            let xor = byte_a(i) ^ byte_b(i);
+            (xor != 0).then(|| i as u16 * 8 + xor.leading_zeros() as u16)
+        })
+}
+
+/// Width of the value index stored in each leaf; see [`HotTree::with_value_index_width`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValueIndexWidth {
+    /// 2-byte index: at most 65536 value slots over the tree's lifetime.
+    U16,
+    /// 4-byte index.
+    #[default]
+    U32,
+}
+
+impl ValueIndexWidth {
+    #[inline]
+    fn bytes(self) -> usize {
+        match self {
+            ValueIndexWidth::U16 => 2,
+            ValueIndexWidth::U32 => 4,
+        }
+    }
+
+    #[inline]
+    fn max_slots(self) -> usize {
+        match self {
+            ValueIndexWidth::U16 => u16::MAX as usize + 1,
+            ValueIndexWidth::U32 => u32::MAX as usize + 1,
+        }
+    }
+}
+
+/// How the node and leaf arenas grow when full; see [`HotTree::with_growth_policy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GrowthPolicy {
+    /// Double the capacity, or grow just enough if doubling is not.
+    #[default]
+    Doubling,
+    /// Grow by a fixed number of bytes, bounding slack to one chunk per arena.
+    FixedChunk(usize),
+    /// Grow capacity by the given factor (> 1.0).
+    Factor(f64),
+}
+
+impl GrowthPolicy {
+    /// Make room for `additional` more elements in `buf` according to the policy.
+    #[inline]
+    fn reserve<T>(self, buf: &mut Vec<T>, additional: usize) {
+        if buf.capacity() - buf.len() >= additional {
+            return;
+        }
+        match self {
+            GrowthPolicy::Doubling => {
+                let target = (buf.capacity() * 2).max(buf.len() + additional);
+                buf.reserve_exact(target - buf.len());
+            }
+            GrowthPolicy::FixedChunk(chunk) => {
+                let chunk = (chunk / std::mem::size_of::<T>()).max(1);
+                buf.reserve_exact(additional.max(chunk))
+            }
+            GrowthPolicy::Factor(factor) => {
+                let target = (buf.capacity() as f64 * factor) as usize;
+                let target = target.max(buf.len() + additional);
+                buf.reserve_exact(target - buf.len());
+            }
+        }
+    }
+
+    /// Capacity that `reserve` leaves a buffer of `len` elements of `elem_size` bytes at
+    /// after making room for `additional` more.
+    fn grown_capacity(self, len: usize, cap: usize, additional: usize, elem_size: usize) -> usize {
+        if cap - len >= additional {
+            return cap;
+        }
+        match self {
+            GrowthPolicy::Doubling => (cap * 2).max(len + additional),
+            GrowthPolicy::FixedChunk(chunk) => {
+                len + additional.max((chunk / elem_size.max(1)).max(1))
+            }
+            GrowthPolicy::Factor(factor) => ((cap as f64 * factor) as usize).max(len + additional),
+        }
+    }
+}
+
+/// Values small enough to live inside their leaf record; see [`HotTree::with_inline_values`].
+///
+/// # Safety
+///
+/// Implementors must be at most 4 bytes, contain no padding bytes, and accept an all-zero
+/// bit pattern.
+pub unsafe trait InlineValue: Copy {}
+
+unsafe impl InlineValue for u8 {}
+unsafe impl InlineValue for u16 {}
+unsafe impl InlineValue for u32 {}
+unsafe impl InlineValue for i8 {}
+unsafe impl InlineValue for i16 {}
+unsafe impl InlineValue for i32 {}
+unsafe impl InlineValue for f32 {}
+unsafe impl InlineValue for bool {}
+unsafe impl InlineValue for char {}
+unsafe impl InlineValue for [u8; 1] {}
+unsafe impl InlineValue for [u8; 2] {}
+unsafe impl InlineValue for [u8; 3] {}
+unsafe impl InlineValue for [u8; 4] {}
+
+/// Per-tree settings chosen through the `HotTree::with_*` builders.
+#[derive(Clone, Copy, Debug)]
+struct Config {
+    max_key_len: usize,
+    max_height: u8,
+    /// Leaves reference a pooled tail instead of storing it inline.
+    intern_suffixes: bool,
+    value_index_width: ValueIndexWidth,
+    growth: GrowthPolicy,
+    /// Byte size the leaf and node arenas may reach; fixed by the pointer offset width
+    /// outside of tests.
+    arena_limit: usize,
+    /// Leaves hold the value bytes instead of an index into `values`; only set for
+    /// `V: InlineValue`.
+    inline_values: bool,
+    /// Bound on [`HotTree::memory_usage`] enforced by `try_insert`.
+    memory_budget: Option<usize>,
+    /// Every key has this length, so leaves store it bare; see [`FixedKeyHot`].
+    fixed_key_len: Option<usize>,
+    /// Keys shorter than this are stored without looking for a prefix.
+    min_key_len_for_prefix: usize,
+    simd: SimdSupport,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_key_len: MAX_KEY_LEN,
+            max_height: u8::MAX,
+            intern_suffixes: false,
+            value_index_width: ValueIndexWidth::U32,
+            growth: GrowthPolicy::Doubling,
+            arena_limit: Ptr::OFFSET_MASK as usize + 1,
+            inline_values: false,
+            memory_budget: None,
+            fixed_key_len: None,
+            min_key_len_for_prefix: 0,
+            simd: SimdSupport::detect(),
+        }
+    }
+}
+
+// =============================================================================
+// Errors
+// =============================================================================
+
+/// Error returned by the fallible `try_*` operations on [`HotTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HotError {
+    /// The key is longer than the tree's configured maximum.
+    KeyTooLong { len: usize, max: usize },
+    /// Inserting a new key could grow the trie past its configured maximum height.
+    HeightLimit { max: u8 },
+    /// Every value slot addressable by the tree's value index width is in use.
+    ValueIndexOverflow { max: usize },
+    /// The leaf or node arena could outgrow the `max` bytes addressable by a child pointer.
+    ArenaFull { max: usize },
+    /// The key is not the fixed length a [`FixedKeyHot`] requires.
+    WrongKeyLength { len: usize, expected: usize },
+    /// Inserting the key could grow [`HotTree::memory_usage`] past the tree's `max`-byte
+    /// budget.
+    MemoryBudget { max: usize },
+}
+
+impl std::fmt::Display for HotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HotError::KeyTooLong { len, max } => {
+                write!(f, "key length {len} exceeds maximum of {max} bytes")
+            }
+            HotError::HeightLimit { max } => {
+                write!(f, "insert would exceed maximum trie height of {max}")
+            }
+            HotError::ValueIndexOverflow { max } => {
+                write!(f, "value index space of {max} slots exhausted")
+            }
+            HotError::ArenaFull { max } => {
+                write!(
+                    f,
+                    "insert could grow an arena past its {max}-byte offset limit"
+                )
+            }
+            HotError::WrongKeyLength { len, expected } => {
+                write!(
+                    f,
+                    "key length {len} differs from the fixed length {expected}"
+                )
+            }
+            HotError::MemoryBudget { max } => {
+                write!(
+                    f,
+                    "insert could grow memory usage past its {max}-byte budget"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for HotError {}
+
+/// Inconsistency found by [`HotTree::try_get`] in a tree whose arenas do not describe a
+/// valid trie, such as one loaded from untrusted bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Corruption {
+    /// A child pointer names a node outside the node arena, or the node there has an
+    /// unknown tag or an impossible entry count.
+    Node { off: u64 },
+    /// A child pointer names a leaf record that runs past the end of the leaf arena.
+    Leaf { off: u64 },
+    /// A leaf's value index has no value slot.
+    ValueIndex { idx: usize },
+    /// No entry of a node matches the key's partial key, or the descent ran deeper than
+    /// any tree can be.
+    Descent,
+}
+
+impl std::fmt::Display for Corruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Corruption::Node { off } => write!(f, "invalid node at offset {off}"),
+            Corruption::Leaf { off } => write!(f, "leaf record at offset {off} out of bounds"),
+            Corruption::ValueIndex { idx } => write!(f, "value index {idx} out of bounds"),
+            Corruption::Descent => write!(f, "descent does not reach a leaf"),
+        }
+    }
+}
+
+impl std::error::Error for Corruption {}
+
+// =============================================================================
+// Prefix hashing
+// =============================================================================
+
+/// 64-bit FNV-1a, the default hasher for the prefix table.
+///
+/// Fast and deterministic, but trivially collidable by an adversary. Deployments that index
+/// untrusted keys should use [`HotTree::with_hasher`] with a keyed hasher such as
+/// [`std::collections::hash_map::RandomState`].
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    #[inline]
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Default [`BuildHasher`] for [`HotTree`]'s prefix table.
+pub type FnvBuildHasher = BuildHasherDefault<FnvHasher>;
 
 // =============================================================================
 // Bit utilities (PEXT helpers + bit indexing)
@@ -95,15 +501,44 @@ unsafe fn pext_u64_bmi2(value: u64, mask: u64) -> u64 {
     core::arch::x86_64::_pext_u64(value, mask)
 }
 
+/// CPU extensions the partial-key code may use, detected once when a tree is built so
+/// lookups branch on a stored flag instead of re-running feature detection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SimdSupport {
+    bmi2: bool,
+    avx2: bool,
+}
+
+impl SimdSupport {
+    fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            Self {
+                bmi2: std::is_x86_feature_detected!("bmi2"),
+                avx2: std::is_x86_feature_detected!("avx2"),
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            Self {
+                bmi2: false,
+                avx2: false,
+            }
+        }
+    }
+}
+
 #[inline]
-fn pext_u64(value: u64, mask: u64) -> u64 {
+fn pext_u64(value: u64, mask: u64, simd: SimdSupport) -> u64 {
     #[cfg(target_arch = "x86_64")]
     {
-        if std::is_x86_feature_detected!("bmi2") {
-            // SAFETY: feature detected at runtime.
+        if simd.bmi2 {
+            // SAFETY: `SimdSupport` only reports features detected at runtime.
             return unsafe { pext_u64_bmi2(value, mask) };
         }
     }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = simd;
     pext_u64_fallback(value, mask)
 }
 
@@ -118,7 +553,7 @@ fn pext_u64(value: u64, mask: u64) -> u64 {
 /// - Bit 39 = 0: node (byte offset into `nodes`)
 /// - Bit 38 = 1: tombstone (only meaningful for leaf pointers when `V` is ZST)
 /// - Special: 0xFF_FFFF_FFFF (40-bit all 1s) = NULL
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 struct Ptr(u64);
 
 impl Ptr {
@@ -202,7 +637,7 @@ impl SingleMaskPartialKeyMapping {
         let mut mask = self.extraction_mask;
         while mask != 0 {
             let lsb = mask & mask.wrapping_neg();
-            let bit_pos = lsb.trailing_zeros() as u32; // 0..63, where 0 is LSB
+            let bit_pos = lsb.trailing_zeros(); // 0..63, where 0 is LSB
             mask ^= lsb;
 
             let idx_from_msb = 63u32 - bit_pos;
@@ -248,18 +683,20 @@ impl SingleMaskPartialKeyMapping {
     }
 
     #[inline]
-    fn extract_u32(self, key: &[u8]) -> u32 {
+    #[allow(clippy::needless_range_loop)]
+    fn extract_u32(self, key: &[u8], simd: SimdSupport) -> u32 {
         let off = self.byte_offset as usize;
         let mut bytes = [0u8; 8];
         for i in 0..8 {
-            bytes[i] = key.get(off + i).copied().unwrap_or(0);
+            bytes[i] = key_byte(key, off + i);
         }
         let word = u64::from_be_bytes(bytes);
-        pext_u64(word, self.extraction_mask) as u32
+        pext_u64(word, self.extraction_mask, simd) as u32
     }
 
     #[inline]
-    fn prefix_mask_u32(self, discriminative_bit: u16) -> u32 {
+    #[allow(clippy::needless_range_loop)]
+    fn prefix_mask_u32(self, discriminative_bit: u16, simd: SimdSupport) -> u32 {
         let disc_byte = bit_byte_index(discriminative_bit);
         let disc_bit_msb0 = bit_in_byte_msb0(discriminative_bit);
 
@@ -276,7 +713,7 @@ impl SingleMaskPartialKeyMapping {
         }
 
         let word = u64::from_be_bytes(bytes);
-        pext_u64(word, self.extraction_mask) as u32
+        pext_u64(word, self.extraction_mask, simd) as u32
     }
 }
 
@@ -328,6 +765,7 @@ impl<const N: usize> MultiMaskPartialKeyMapping<N> {
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn try_from_bits(discriminative_bits: &[u16]) -> Option<Self> {
         debug_assert!(!discriminative_bits.is_empty());
 
@@ -421,7 +859,7 @@ impl<const N: usize> MultiMaskPartialKeyMapping<N> {
     }
 
     #[inline]
-    fn extract_u32(self, key: &[u8]) -> u32 {
+    fn extract_u32(self, key: &[u8], simd: SimdSupport) -> u32 {
         let mut out = 0u32;
         for g in 0..N {
             let pos = self.positions_be[g].to_be_bytes();
@@ -432,11 +870,11 @@ impl<const N: usize> MultiMaskPartialKeyMapping<N> {
 
             let mut gathered = [0u8; 8];
             for i in 0..8 {
-                let abs_byte = self.base_bytes[g] + u16::from(pos[i]);
-                gathered[i] = key.get(abs_byte as usize).copied().unwrap_or(0);
+                let abs_byte = usize::from(self.base_bytes[g]) + usize::from(pos[i]);
+                gathered[i] = key_byte(key, abs_byte);
             }
             let word = u64::from_be_bytes(gathered);
-            let part = pext_u64(word, masks) as u32;
+            let part = pext_u64(word, masks, simd) as u32;
             let bits = masks.count_ones();
             out = (out << bits) | part;
         }
@@ -444,7 +882,7 @@ impl<const N: usize> MultiMaskPartialKeyMapping<N> {
     }
 
     #[inline]
-    fn prefix_mask_u32(self, discriminative_bit: u16) -> u32 {
+    fn prefix_mask_u32(self, discriminative_bit: u16, simd: SimdSupport) -> u32 {
         let disc_byte = bit_byte_index(discriminative_bit);
         let disc_bit_msb0 = bit_in_byte_msb0(discriminative_bit);
 
@@ -469,7 +907,7 @@ impl<const N: usize> MultiMaskPartialKeyMapping<N> {
             }
 
             let word = u64::from_be_bytes(prefix_bytes);
-            let part = pext_u64(word, masks) as u32;
+            let part = pext_u64(word, masks, simd) as u32;
             let bits = masks.count_ones();
             out = (out << bits) | part;
         }
@@ -510,24 +948,24 @@ impl DiscriminativeBitsRepresentation {
     }
 
     #[inline]
-    fn extract_u32(self, key: &[u8]) -> u32 {
+    fn extract_u32(self, key: &[u8], simd: SimdSupport) -> u32 {
         match self {
-            Self::Single(m) => m.extract_u32(key),
-            Self::Multi1(m) => m.extract_u32(key),
-            Self::Multi2(m) => m.extract_u32(key),
-            Self::Multi4(m) => m.extract_u32(key),
-            Self::Multi8(m) => m.extract_u32(key),
+            Self::Single(m) => m.extract_u32(key, simd),
+            Self::Multi1(m) => m.extract_u32(key, simd),
+            Self::Multi2(m) => m.extract_u32(key, simd),
+            Self::Multi4(m) => m.extract_u32(key, simd),
+            Self::Multi8(m) => m.extract_u32(key, simd),
         }
     }
 
     #[inline]
-    fn prefix_mask_u32(self, discriminative_bit: u16) -> u32 {
+    fn prefix_mask_u32(self, discriminative_bit: u16, simd: SimdSupport) -> u32 {
         match self {
-            Self::Single(m) => m.prefix_mask_u32(discriminative_bit),
-            Self::Multi1(m) => m.prefix_mask_u32(discriminative_bit),
-            Self::Multi2(m) => m.prefix_mask_u32(discriminative_bit),
-            Self::Multi4(m) => m.prefix_mask_u32(discriminative_bit),
-            Self::Multi8(m) => m.prefix_mask_u32(discriminative_bit),
+            Self::Single(m) => m.prefix_mask_u32(discriminative_bit, simd),
+            Self::Multi1(m) => m.prefix_mask_u32(discriminative_bit, simd),
+            Self::Multi2(m) => m.prefix_mask_u32(discriminative_bit, simd),
+            Self::Multi4(m) => m.prefix_mask_u32(discriminative_bit, simd),
+            Self::Multi8(m) => m.prefix_mask_u32(discriminative_bit, simd),
         }
     }
 
@@ -542,30 +980,33 @@ impl DiscriminativeBitsRepresentation {
     }
 
     fn build_minimal(discriminative_bits: &[u16]) -> Self {
+        Self::try_build_minimal(discriminative_bits).unwrap_or_else(|| {
+            panic!(
+                "cannot build discriminative-bits representation: bits={}, bytes may span >255, require too many extraction groups, or require >64 distinct bytes",
+                discriminative_bits.len()
+            )
+        })
+    }
+
+    fn try_build_minimal(discriminative_bits: &[u16]) -> Option<Self> {
         let bits_needed = discriminative_bits.len();
         if let Some(m) = SingleMaskPartialKeyMapping::try_from_bits(discriminative_bits) {
-            return Self::Single(m);
+            return Some(Self::Single(m));
         }
         if let Some(m) = MultiMaskPartialKeyMapping::<1>::try_from_bits(discriminative_bits) {
-            return Self::Multi1(m);
+            return Some(Self::Multi1(m));
         }
         // The original HOT node type set does not include a 16-byte MultiMask with 32-bit partial
         // keys; for >16 key bits we directly use the 32-byte (4-mask) representation.
         if bits_needed <= 16 {
             if let Some(m) = MultiMaskPartialKeyMapping::<2>::try_from_bits(discriminative_bits) {
-                return Self::Multi2(m);
+                return Some(Self::Multi2(m));
             }
         }
         if let Some(m) = MultiMaskPartialKeyMapping::<4>::try_from_bits(discriminative_bits) {
-            return Self::Multi4(m);
-        }
-        if let Some(m) = MultiMaskPartialKeyMapping::<8>::try_from_bits(discriminative_bits) {
-            return Self::Multi8(m);
+            return Some(Self::Multi4(m));
         }
-        panic!(
-            "cannot build discriminative-bits representation: bits={}, bytes may span >255, require too many extraction groups, or require >64 distinct bytes",
-            discriminative_bits.len()
-        );
+        MultiMaskPartialKeyMapping::<8>::try_from_bits(discriminative_bits).map(Self::Multi8)
     }
 }
 
@@ -602,6 +1043,11 @@ const NODE_HEADER_SIZE: usize = 4;
 const PTR_SIZE: usize = 5;
 
 const MAX_NODE_SIZE: usize = 512;
+/// Leaf record bytes beyond the key: ids, suffix length, value index or inline value.
+const MAX_LEAF_OVERHEAD: usize = 2 + 2 + 3 + 4 + 3;
+/// Buckets in [`HotTree::arena_free_histogram`]: bucket `i` counts holes of
+/// `2^i..2^(i+1)` bytes, so the last bucket holds `MAX_NODE_SIZE`-byte holes.
+pub const ARENA_FREE_BUCKETS: usize = MAX_NODE_SIZE.trailing_zeros() as usize + 1;
 
 #[inline]
 fn hot_mapping_size(tag: u8) -> usize {
@@ -652,32 +1098,147 @@ fn hot_is_hot_node(tag: u8) -> bool {
     )
 }
 
+fn node_tag_name(tag: u8) -> &'static str {
+    match tag {
+        NODE_TWO_ENTRIES => "TwoEntries",
+        NODE_HOT_SINGLE_MASK_U8 => "SingleMaskU8",
+        NODE_HOT_SINGLE_MASK_U16 => "SingleMaskU16",
+        NODE_HOT_SINGLE_MASK_U32 => "SingleMaskU32",
+        NODE_HOT_MULTI_MASK_8B_U8 => "MultiMask8U8",
+        NODE_HOT_MULTI_MASK_8B_U16 => "MultiMask8U16",
+        NODE_HOT_MULTI_MASK_8B_U32 => "MultiMask8U32",
+        NODE_HOT_MULTI_MASK_16B_U16 => "MultiMask16U16",
+        NODE_HOT_MULTI_MASK_32B_U32 => "MultiMask32U32",
+        NODE_HOT_MULTI_MASK_64B_U8 => "MultiMask64U8",
+        NODE_HOT_MULTI_MASK_64B_U16 => "MultiMask64U16",
+        NODE_HOT_MULTI_MASK_64B_U32 => "MultiMask64U32",
+        other => panic!("unknown node tag {other}"),
+    }
+}
+
 #[inline]
 fn hot_node_size(tag: u8, n: usize) -> usize {
     debug_assert!(hot_is_hot_node(tag));
     NODE_HEADER_SIZE + hot_mapping_size(tag) + hot_partial_key_size(tag) * n + PTR_SIZE * n
 }
 
+/// Append-only byte buffer for leaf records.
+///
+/// Backed by `u32` words so that inline values (at most 4 bytes) can be borrowed in place
+/// at offsets aligned for their type.
+#[derive(Clone, Default)]
+struct LeafBytes {
+    words: Vec<u32>,
+    len: usize,
+}
+
+impl LeafBytes {
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.words.capacity() * 4
+    }
+
+    fn reserve(&mut self, growth: GrowthPolicy, additional: usize) {
+        let words = (self.len + additional).div_ceil(4) - self.words.len();
+        growth.reserve(&mut self.words, words);
+    }
+
+    fn reserve_exact(&mut self, additional: usize) {
+        let words = (self.len + additional).div_ceil(4) - self.words.len();
+        self.words.reserve_exact(words);
+    }
+
+    /// Append `n` zero bytes, returning their offset.
+    fn extend_zeroed(&mut self, n: usize) -> usize {
+        let off = self.len;
+        self.len += n;
+        self.words.resize(self.len.div_ceil(4), 0);
+        off
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        let off = self.extend_zeroed(bytes.len());
+        self[off..].copy_from_slice(bytes);
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.extend_from_slice(&[byte]);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.words.shrink_to_fit();
+    }
+}
+
+impl std::ops::Deref for LeafBytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        // SAFETY: `words` holds at least `len` initialized bytes, and `u8` has no invalid
+        // bit patterns or alignment requirement.
+        unsafe { std::slice::from_raw_parts(self.words.as_ptr().cast::<u8>(), self.len) }
+    }
+}
+
+impl std::ops::DerefMut for LeafBytes {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as for `deref`, with exclusive access through `&mut self`.
+        unsafe { std::slice::from_raw_parts_mut(self.words.as_mut_ptr().cast::<u8>(), self.len) }
+    }
+}
+
 /// Node arena for HOT nodes, with simple size-class free lists.
 #[derive(Clone)]
 struct NodeArena {
     data: Vec<u8>,
     /// Free lists by exact node byte size.
     free: Vec<Vec<u64>>,
+    growth: GrowthPolicy,
 }
 
 impl NodeArena {
-    fn new() -> Self {
+    fn new(growth: GrowthPolicy) -> Self {
         Self {
             data: Vec::new(),
             free: (0..=MAX_NODE_SIZE).map(|_| Vec::new()).collect(),
+            growth,
         }
     }
 
+    /// Append `size` zeroed bytes, returning their offset.
+    #[inline]
+    fn alloc_tail(&mut self, size: usize) -> u64 {
+        let off = self.data.len() as u64;
+        assert!(
+            off <= Ptr::OFFSET_MASK,
+            "NODE ARENA OVERFLOW: nodes.len()={off} exceeds max offset {}",
+            Ptr::OFFSET_MASK
+        );
+        self.growth.reserve(&mut self.data, size);
+        self.data.resize(self.data.len() + size, 0);
+        off
+    }
+
     fn capacity(&self) -> usize {
         self.data.capacity() + self.free.iter().map(|v| v.capacity() * 8).sum::<usize>()
     }
 
+    /// Bytes of arena in use or on the free lists.
+    fn used_bytes(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Bytes held by freed nodes awaiting reuse.
+    fn free_bytes(&self) -> usize {
+        self.free
+            .iter()
+            .enumerate()
+            .map(|(size, offs)| size * offs.len())
+            .sum()
+    }
+
     fn shrink_to_fit(&mut self) {
         self.data.shrink_to_fit();
         for v in &mut self.free {
@@ -726,14 +1287,12 @@ impl NodeArena {
 
     fn alloc_two_entries_node(&mut self, disc: u16, height: u8, left: Ptr, right: Ptr) -> u64 {
         const SIZE: usize = NODE_HEADER_SIZE + 2 + 2 * PTR_SIZE;
-        debug_assert!(SIZE <= MAX_NODE_SIZE);
+        const { assert!(SIZE <= MAX_NODE_SIZE) };
 
         let off = if let Some(off) = self.free[SIZE].pop() {
             off
         } else {
-            let off = self.data.len() as u64;
-            self.data.resize(self.data.len() + SIZE, 0);
-            off
+            self.alloc_tail(SIZE)
         };
 
         let o = off as usize;
@@ -793,6 +1352,7 @@ impl NodeArena {
         self.data[at..at + 8].copy_from_slice(&b);
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn alloc_hot_node(
         &mut self,
         tag: u8,
@@ -804,7 +1364,7 @@ impl NodeArena {
         debug_assert!(hot_is_hot_node(tag));
         let n = child_ptrs.len();
         debug_assert_eq!(sparse_partial_keys.len(), n);
-        debug_assert!(n >= 2 && n <= MAX_COMPOUND_ENTRIES);
+        debug_assert!((2..=MAX_COMPOUND_ENTRIES).contains(&n));
 
         let size = hot_node_size(tag, n);
         debug_assert!(size <= MAX_NODE_SIZE);
@@ -812,9 +1372,7 @@ impl NodeArena {
         let off = if let Some(off) = self.free[size].pop() {
             off
         } else {
-            let off = self.data.len() as u64;
-            self.data.resize(self.data.len() + size, 0);
-            off
+            self.alloc_tail(size)
         };
 
         let o = off as usize;
@@ -952,6 +1510,7 @@ impl NodeArena {
     }
 
     #[inline]
+    #[allow(clippy::needless_range_loop)]
     fn hot_mapping(&self, off: u64) -> DiscriminativeBitsRepresentation {
         let tag = self.tag(off);
         debug_assert!(hot_is_hot_node(tag));
@@ -1136,6 +1695,7 @@ impl NodeArena {
     fn free_node(&mut self, off: u64) {
         let size = self.node_size(off);
         debug_assert!(size <= MAX_NODE_SIZE);
+        GrowthPolicy::Doubling.reserve(&mut self.free[size], 1);
         self.free[size].push(off);
     }
 }
@@ -1151,18 +1711,30 @@ impl NodeArena {
 /// - HOT compound nodes (2..=32 entries)
 /// - Adaptive prefix learning from natural delimiters
 /// - ZST value optimization
-pub struct HotTree<V> {
+///
+/// `S` hashes learned prefixes for the prefix table; see [`HotTree::with_hasher`].
+pub struct HotTree<V, S = FnvBuildHasher> {
     // === Prefix compression ===
     /// Prefix pool: contiguous storage of all prefixes
     prefix_pool: Vec<u8>,
     /// Offset of each prefix in pool (prefix_id -> offset)
     prefix_offsets: Vec<u32>,
+    /// Live leaves using each prefix (as prefix or interned tail), parallel to
+    /// `prefix_offsets`; unused prefixes are dropped by [`HotTree::compact`].
+    prefix_refs: Vec<u32>,
     /// Map from prefix hash to prefix_id for fast lookup
     prefix_hash: HashMap<u64, u16>,
+    /// Hasher for prefix bytes (keys of `prefix_hash`)
+    hash_builder: S,
+    /// Prefixes shared with other trees, holding ids `1..=len`; ids of this tree's own
+    /// prefixes follow them.
+    shared_prefixes: Option<Arc<SharedPrefixPool>>,
 
     // === Leaf storage ===
-    /// Leaf arena: [prefix_id:2][suffix_len:1-3][suffix...][value_idx:4]
-    leaves: Vec<u8>,
+    /// Leaf arena: [prefix_id:2][suffix_len:1-3][suffix...][value_idx:2|4]
+    /// (with [tail_id:2] after prefix_id when suffix interning is enabled, and the aligned
+    /// value itself in place of value_idx with inline values)
+    leaves: LeafBytes,
 
     // === Values ===
     values: Vec<Option<V>>,
@@ -1174,22 +1746,89 @@ pub struct HotTree<V> {
     nodes: NodeArena,
     root: Ptr,
     count: usize,
+    config: Config,
+    /// Unique to this tree and not shared by its clones; see [`PreparedKey`].
+    id: u64,
+    /// Bumped whenever a key is added or removed; see [`PreparedKey`].
+    generation: u64,
+    /// Bytes of leaf records in `leaves` no longer reachable from the trie.
+    dead_leaf_bytes: usize,
+    /// Bumped by [`HotTree::compact_leaves`], which relocates every leaf; see [`Handle`].
+    leaf_epoch: u32,
+    /// Optional filter of inserted key hashes; see [`HotTree::enable_bloom`].
+    bloom: Option<BloomFilter>,
+    /// Change generation of each value slot, parallel to `values`, when tracking changes;
+    /// see [`HotTree::enable_change_tracking`].
+    change_stamps: Option<Vec<u64>>,
+    /// Generation the next value write is stamped with.
+    change_generation: u64,
+    /// Sightings of not-yet-interned tails; see [`HotTree::with_suffix_interning`].
+    tail_sketch: TailSketch,
 
     _marker: PhantomData<V>,
 }
 
 impl<V> HotTree<V> {
     pub fn new() -> Self {
+        Self::with_hasher(FnvBuildHasher::default())
+    }
+
+    /// Create an empty tree that compresses keys against `pool` before learning
+    /// prefixes of its own.
+    pub fn new_with_pool(pool: Arc<SharedPrefixPool>) -> Self {
+        Self::new().with_prefix_pool(pool)
+    }
+}
+
+impl<V: InlineValue, S: BuildHasher> HotTree<V, S> {
+    /// Store each value in its leaf record instead of behind a value index.
+    ///
+    /// For a `u32` this replaces the 4-byte index and 8-byte `Option<u32>` slot with the
+    /// 4 value bytes (plus up to 3 bytes of alignment padding). Changes the leaf encoding, so
+    /// it must be chosen before the first insert.
+    pub fn with_inline_values(mut self) -> Self {
+        assert!(
+            std::mem::size_of::<V>() <= 4,
+            "inline values must be at most 4 bytes"
+        );
+        assert!(
+            self.leaves.is_empty(),
+            "inline values must be configured before the first insert"
+        );
+        self.config.inline_values = true;
+        self
+    }
+}
+
+impl<V, S: BuildHasher> HotTree<V, S> {
+    /// Create an empty tree whose prefix table hashes prefixes with `hash_builder`.
+    ///
+    /// A prefix whose hash collides with an already-learned prefix is stored uncompressed,
+    /// so an adversary who can predict the hash can quietly defeat prefix compression. Use a
+    /// keyed hasher (e.g. `RandomState`) when keys come from untrusted input.
+    pub fn with_hasher(hash_builder: S) -> Self {
         let mut tree = Self {
             prefix_pool: Vec::new(),
             prefix_offsets: Vec::new(),
+            prefix_refs: Vec::new(),
             prefix_hash: HashMap::new(),
-            leaves: Vec::new(),
+            hash_builder,
+            shared_prefixes: None,
+            leaves: LeafBytes::default(),
             values: Vec::new(),
             zst_values: Vec::new(),
-            nodes: NodeArena::new(),
+            nodes: NodeArena::new(GrowthPolicy::Doubling),
             root: Ptr::NULL,
             count: 0,
+            config: Config::default(),
+            id: next_tree_id(),
+            generation: 0,
+            dead_leaf_bytes: 0,
+            leaf_epoch: 0,
+            bloom: None,
+            change_stamps: None,
+            change_generation: 0,
+            tail_sketch: TailSketch::default(),
             _marker: PhantomData,
         };
         // Register empty prefix as ID 0
@@ -1197,6 +1836,144 @@ impl<V> HotTree<V> {
         tree
     }
 
+    /// Reject keys longer than `max_key_len` bytes in [`HotTree::try_insert`].
+    ///
+    /// Bounds the per-insert bit scan. Defaults to (and may not exceed) [`MAX_KEY_LEN`].
+    pub fn with_max_key_len(mut self, max_key_len: usize) -> Self {
+        assert!(
+            max_key_len <= MAX_KEY_LEN,
+            "max_key_len {max_key_len} exceeds MAX_KEY_LEN ({MAX_KEY_LEN})"
+        );
+        self.config.max_key_len = max_key_len;
+        self
+    }
+
+    /// Reject new keys in [`HotTree::try_insert`] once the root has reached `max_height`.
+    ///
+    /// Node heights are stored in a byte, so the default of 255 is also the hard limit.
+    /// An insert raises the root by at most one level, so the check is made before it.
+    pub fn with_max_height(mut self, max_height: u8) -> Self {
+        assert!(max_height >= 1, "max_height must be at least 1");
+        self.config.max_height = max_height;
+        self
+    }
+
+    /// Store keys shorter than `len` bytes without a prefix, skipping the delimiter scan.
+    ///
+    /// Short keys rarely share enough bytes for a 2-byte prefix id to pay off, so for
+    /// short-key workloads this speeds up inserts at little cost in memory. By default
+    /// every key is scanned. Affects only keys inserted afterwards.
+    pub fn with_min_key_len_for_prefix(mut self, len: usize) -> Self {
+        self.config.min_key_len_for_prefix = len;
+        self
+    }
+
+    /// Use BMI2 and AVX2 for partial-key extraction and node search where the CPU has them
+    /// (the default), or force the portable scalar code with `false`.
+    ///
+    /// Both paths give identical results and node layouts; turning SIMD off is for
+    /// differential testing and for reproducing timings across machines. `true` never
+    /// enables an extension the CPU lacks.
+    pub fn with_simd(mut self, enabled: bool) -> Self {
+        self.config.simd = if enabled {
+            SimdSupport::detect()
+        } else {
+            SimdSupport {
+                bmi2: false,
+                avx2: false,
+            }
+        };
+        self
+    }
+
+    /// Pool recurring key tails (the bytes from the last `/`, `:` or `\` on) alongside
+    /// learned prefixes, so leaves store a 2-byte tail id instead of the tail bytes.
+    ///
+    /// A tail is pooled once it has been seen on several keys; until then, and for tails
+    /// that never recur, keys store it inline. Saves memory when many keys end the same way
+    /// (e.g. `…:active`) at the cost of an extra indirection per key comparison and 2 bytes
+    /// per leaf otherwise. Changes the leaf encoding, so it must be chosen before the first
+    /// insert.
+    pub fn with_suffix_interning(mut self, enabled: bool) -> Self {
+        assert!(
+            self.leaves.is_empty(),
+            "suffix interning must be configured before the first insert"
+        );
+        self.config.intern_suffixes = enabled;
+        self
+    }
+
+    /// Store value indices in leaves with `width` bytes.
+    ///
+    /// [`ValueIndexWidth::U16`] saves 2 bytes per key for small maps, but value slots are
+    /// not reused after removal, so [`HotTree::try_insert`] fails once 65536 keys have been
+    /// inserted over the tree's lifetime. Changes the leaf encoding, so it must be chosen
+    /// before the first insert.
+    pub fn with_value_index_width(mut self, width: ValueIndexWidth) -> Self {
+        assert!(
+            self.leaves.is_empty(),
+            "value index width must be configured before the first insert"
+        );
+        self.config.value_index_width = width;
+        self
+    }
+
+    /// Store every key bare in its leaf, with no prefix id or length; every key inserted
+    /// must be exactly `len` bytes. Used by [`FixedKeyHot`], which checks key lengths.
+    pub(crate) fn with_fixed_key_len(mut self, len: usize) -> Self {
+        assert!(
+            len <= MAX_KEY_LEN,
+            "fixed key length {len} exceeds MAX_KEY_LEN ({MAX_KEY_LEN})"
+        );
+        assert!(
+            self.leaves.is_empty(),
+            "fixed key length must be configured before the first insert"
+        );
+        self.config.fixed_key_len = Some(len);
+        self.config.max_key_len = len;
+        self
+    }
+
+    /// Compress keys against the prefixes in `pool`, shared with other trees, before
+    /// learning prefixes of its own. Changes prefix ids, so it must be chosen before the
+    /// first insert.
+    pub fn with_prefix_pool(mut self, pool: Arc<SharedPrefixPool>) -> Self {
+        assert!(
+            self.leaves.is_empty() && self.prefix_offsets.len() == 1,
+            "prefix pool must be configured before the first insert"
+        );
+        self.shared_prefixes = Some(pool);
+        self
+    }
+
+    /// Grow the node and leaf arenas according to `policy` instead of doubling.
+    ///
+    /// `FixedChunk` bounds the unused capacity of each arena to one chunk, avoiding the
+    /// transient 2x footprint of doubling a very large arena.
+    pub fn with_growth_policy(mut self, policy: GrowthPolicy) -> Self {
+        match policy {
+            GrowthPolicy::Doubling => {}
+            GrowthPolicy::FixedChunk(chunk) => assert!(chunk > 0, "chunk size must be non-zero"),
+            GrowthPolicy::Factor(factor) => assert!(factor > 1.0, "growth factor must exceed 1.0"),
+        }
+        self.config.growth = policy;
+        self.nodes.growth = policy;
+        self
+    }
+
+    /// Reject new keys in [`HotTree::try_insert`] with [`HotError::MemoryBudget`] once
+    /// inserting them could grow [`HotTree::memory_usage`] past `bytes`.
+    ///
+    /// Before each new key the tree bounds what the insert could allocate (its leaf record,
+    /// rewritten nodes, a value slot and any learned prefix, at the buffers' next capacity),
+    /// so the estimate is conservative and rejection comes somewhat before the budget is
+    /// reached. Removing keys does not release capacity by itself; follow removals with
+    /// [`HotTree::compact_leaves`] or [`HotTree::shrink_to_fit`] to make room.
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.config.memory_budget = Some(bytes);
+        self
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.count
@@ -1207,39 +1984,513 @@ impl<V> HotTree<V> {
         self.count == 0
     }
 
+    /// Nodes on the longest root-to-leaf path; 0 for an empty tree or a single key.
+    pub fn height(&self) -> u8 {
+        self.ptr_height(self.root)
+    }
+
     pub fn memory_usage(&self) -> usize {
         self.prefix_pool.capacity()
             + self.prefix_offsets.capacity() * 4
+            + self.prefix_refs.capacity() * 4
             + self.prefix_hash.capacity() * 16
             + self.leaves.capacity()
             + self.values.capacity() * std::mem::size_of::<Option<V>>()
             + self.zst_values.capacity() * std::mem::size_of::<V>()
             + self.nodes.capacity()
+            + self.bloom.as_ref().map_or(0, BloomFilter::memory_usage)
+            + self.change_stamps.as_ref().map_or(0, |s| s.capacity() * 8)
+            + self.tail_sketch.memory_usage()
     }
 
-    pub fn shrink_to_fit(&mut self) {
-        self.prefix_pool.shrink_to_fit();
-        self.prefix_offsets.shrink_to_fit();
-        self.prefix_hash.shrink_to_fit();
-        self.leaves.shrink_to_fit();
-        self.values.shrink_to_fit();
-        self.zst_values.shrink_to_fit();
-        self.nodes.shrink_to_fit();
+    /// [`HotTree::memory_usage`] plus `value_size(v)` for every live value, for values that
+    /// own heap memory (e.g. pass `|s: &String| s.capacity()`).
+    pub fn deep_memory_usage<F: Fn(&V) -> usize>(&self, value_size: F) -> usize {
+        let owned: usize = if std::mem::size_of::<V>() == 0 {
+            self.zst_values.iter().map(&value_size).sum()
+        } else {
+            self.values.iter().flatten().map(&value_size).sum()
+        };
+        self.memory_usage() + owned
+    }
+
+    /// Bytes held by storage that no longer backs a live key: freed node slots awaiting
+    /// reuse, leaf records of removed keys, and vacated value slots.
+    ///
+    /// [`HotTree::compact`] reclaims the node portion.
+    pub fn dead_bytes(&self) -> usize {
+        let vacated_values = if std::mem::size_of::<V>() == 0 || self.config.inline_values {
+            0
+        } else {
+            (self.values.len() - self.count) * std::mem::size_of::<Option<V>>()
+        };
+        self.nodes.free_bytes() + self.dead_leaf_bytes + vacated_values
+    }
+
+    /// Fraction of the node arena occupied by freed holes (0.0 for an empty arena).
+    ///
+    /// Holes are reused only by nodes of exactly the same size, so a high ratio means
+    /// [`HotTree::compact`] would reclaim memory.
+    pub fn arena_fragmentation(&self) -> f64 {
+        let used = self.nodes.used_bytes();
+        if used == 0 {
+            return 0.0;
+        }
+        self.nodes.free_bytes() as f64 / used as f64
+    }
+
+    /// Number of freed node holes per power-of-two size bucket; see [`ARENA_FREE_BUCKETS`].
+    pub fn arena_free_histogram(&self) -> [usize; ARENA_FREE_BUCKETS] {
+        let mut buckets = [0usize; ARENA_FREE_BUCKETS];
+        for (size, offs) in self.nodes.free.iter().enumerate() {
+            if size > 0 && !offs.is_empty() {
+                buckets[size.ilog2() as usize] += offs.len();
+            }
+        }
+        buckets
+    }
+
+    /// Count live nodes by layout tag (e.g. `"TwoEntries"`, `"SingleMaskU8"`,
+    /// `"MultiMask64U16"`). Multi-mask tags appear when a node's discriminative bits span
+    /// more than 8 bytes of the key, and their masks cost more per node.
+    pub fn node_tag_histogram(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        let mut stack = vec![self.root];
+        while let Some(ptr) = stack.pop() {
+            if ptr.is_null() || ptr.is_leaf() {
+                continue;
+            }
+            let off = ptr.node_off();
+            *counts
+                .entry(node_tag_name(self.nodes.tag(off)))
+                .or_insert(0) += 1;
+            for i in 0..self.node_entry_count(off) {
+                stack.push(self.node_entry_ptr(off, i));
+            }
+        }
+        counts
+    }
+
+    /// Call `visit` for every node, in pre-order and key order, for external tools that
+    /// mirror the trie's topology. Leaves are not visited but show up in
+    /// [`NodeView::children`].
+    pub fn walk_nodes(&self, mut visit: impl FnMut(NodeView<'_>)) {
+        let mut bits = Vec::new();
+        let mut children = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(ptr) = stack.pop() {
+            if ptr.is_null() || ptr.is_leaf() {
+                continue;
+            }
+            let off = ptr.node_off();
+            let tag = self.nodes.tag(off);
+            let n = self.node_entry_count(off);
+            bits.clear();
+            if tag == NODE_TWO_ENTRIES {
+                bits.push(self.nodes.two_entries_disc(off));
+            } else {
+                self.nodes.hot_mapping(off).discriminative_bits(&mut bits);
+                bits.sort_unstable();
+            }
+            children.clear();
+            children.extend((0..n).map(|i| {
+                if self.node_entry_ptr(off, i).is_leaf() {
+                    ChildKind::Leaf
+                } else {
+                    ChildKind::Node
+                }
+            }));
+            visit(NodeView {
+                tag: node_tag_name(tag),
+                height: self.nodes.height(off),
+                discriminative_bits: &bits,
+                children: &children,
+            });
+            self.push_entries_rev(&mut stack, off, 0, n);
+        }
+    }
+
+    /// Hash of the trie's logical shape, for snapshot-testing how a tree is built.
+    ///
+    /// Covers, in pre-order, each node's tag, entry count, height, discriminative bits and
+    /// partial keys, and each leaf's full key; arena offsets, values and prefix-table
+    /// contents are left out. Uses FNV-1a over little-endian fields, so equal shapes hash
+    /// equally on every platform, and a change to the digest of a tree built the same way
+    /// means its node layout changed.
+    pub fn structure_digest(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        let mut bits = Vec::new();
+        let mut key = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(ptr) = stack.pop() {
+            if ptr.is_null() {
+                continue;
+            }
+            if ptr.is_leaf() {
+                self.get_leaf_key_into(ptr.leaf_off(), &mut key);
+                hasher.write(&[0]);
+                hasher.write(&(key.len() as u32).to_le_bytes());
+                hasher.write(&key);
+                continue;
+            }
+            let off = ptr.node_off();
+            let tag = self.nodes.tag(off);
+            let n = self.node_entry_count(off);
+            hasher.write(&[1, tag, n as u8, self.nodes.height(off)]);
+            bits.clear();
+            if tag == NODE_TWO_ENTRIES {
+                bits.push(self.nodes.two_entries_disc(off));
+            } else {
+                self.nodes.hot_mapping(off).discriminative_bits(&mut bits);
+            }
+            for bit in &bits {
+                hasher.write(&bit.to_le_bytes());
+            }
+            if tag != NODE_TWO_ENTRIES {
+                for i in 0..n {
+                    hasher.write(&self.nodes.hot_partial_key_u32_at(off, i).to_le_bytes());
+                }
+            }
+            self.push_entries_rev(&mut stack, off, 0, n);
+        }
+        hasher.finish()
+    }
+
+    /// Hash of the tree's contents, for checking a persisted or copied tree for corruption.
+    ///
+    /// Folds each key and `value_bytes(value)` into FNV-1a in ascending key order, each
+    /// preceded by its length, so trees with equal entries hash equally however their
+    /// arenas and nodes are laid out (see [`HotTree::structure_digest`] for the shape).
+    ///
+    /// ```
+    /// use hot_rs::HotTree;
+    ///
+    /// let mut t: HotTree<u64> = HotTree::new();
+    /// t.insert("a", 1);
+    /// let sum = t.content_checksum(|v| v.to_le_bytes());
+    /// t.insert("a", 2);
+    /// assert_ne!(t.content_checksum(|v| v.to_le_bytes()), sum);
+    /// ```
+    pub fn content_checksum<'a, B: AsRef<[u8]>>(&'a self, value_bytes: impl Fn(&'a V) -> B) -> u64 {
+        let mut hasher = FnvHasher::default();
+        let mut key = Vec::new();
+        let mut walk = LeafWalk::new(self);
+        while let Some((leaf_off, value)) = walk.next_leaf() {
+            self.get_leaf_key_into(leaf_off, &mut key);
+            let bytes = value_bytes(value);
+            let bytes = bytes.as_ref();
+            hasher.write(&(key.len() as u32).to_le_bytes());
+            hasher.write(&key);
+            hasher.write(&(bytes.len() as u64).to_le_bytes());
+            hasher.write(bytes);
+        }
+        hasher.finish()
+    }
+
+    /// `(min, max, mean)` length of the stored keys, counting shared prefix and suffix
+    /// bytes; `(0, 0, 0.0)` for an empty tree.
+    pub fn key_length_stats(&self) -> (usize, usize, f64) {
+        let mut walk = LeafWalk::new(self);
+        let (mut min, mut max, mut total) = (usize::MAX, 0, 0);
+        while let Some((leaf_off, _)) = walk.next_leaf() {
+            let len = self.leaf_key(leaf_off).len();
+            min = min.min(len);
+            max = max.max(len);
+            total += len;
+        }
+        if self.count == 0 {
+            return (0, 0, 0.0);
+        }
+        (min, max, total as f64 / self.count as f64)
+    }
+
+    /// Reserve value slots for at least `additional` more keys, so a bulk load of known
+    /// size into an existing tree does not regrow them. No-op with inline values.
+    pub fn reserve_values(&mut self, additional: usize) {
+        if std::mem::size_of::<V>() == 0 {
+            self.zst_values.reserve_exact(additional);
+        } else if !self.config.inline_values {
+            self.values.reserve_exact(additional);
+            if let Some(stamps) = &mut self.change_stamps {
+                stamps.reserve_exact(additional);
+            }
+        }
+    }
+
+    /// Reserve room for at least `bytes` more bytes of leaf records. A new key's record
+    /// takes its length minus any learned prefix, plus 3 to 9 bytes of header and value
+    /// index (or the inline value).
+    pub fn reserve_leaf_bytes(&mut self, bytes: usize) {
+        self.leaves.reserve_exact(bytes);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.prefix_pool.shrink_to_fit();
+        self.prefix_offsets.shrink_to_fit();
+        self.prefix_refs.shrink_to_fit();
+        self.prefix_hash.shrink_to_fit();
+        self.leaves.shrink_to_fit();
+        self.values.shrink_to_fit();
+        self.zst_values.shrink_to_fit();
+        self.nodes.shrink_to_fit();
     }
 
-    /// Compact the node arena by rebuilding live nodes into a fresh arena.
+    /// Compact the node arena by rebuilding live nodes into a fresh arena, and drop learned
+    /// prefixes that no live key uses any more.
     ///
     /// This can reduce memory when the arena has accumulated holes due to node
-    /// replacements during insertion. Returns the number of nodes rewritten.
+    /// replacements during insertion, or when removals have emptied whole prefixes (e.g.
+    /// expired sessions under one host). Returns the number of nodes rewritten.
     pub fn compact(&mut self) -> usize {
+        self.compact_prefixes();
         if self.root.is_null() || self.root.is_leaf() {
             return 0;
         }
 
-        let old_nodes = std::mem::replace(&mut self.nodes, NodeArena::new());
-        let mut new_nodes = NodeArena::new();
-        let mut rewritten = 0usize;
+        let growth = self.config.growth;
+        let old_nodes = std::mem::replace(&mut self.nodes, NodeArena::new(growth));
+        let mut new_nodes = NodeArena::new(growth);
+        let (new_root, rewritten) =
+            Self::copy_subtree(&old_nodes, self.root, &mut new_nodes, |leaf| leaf);
+
+        self.nodes = new_nodes;
+        self.root = new_root;
+        rewritten
+    }
+
+    /// Rebuild the leaf arena and value slots from the live keys, dropping the records and
+    /// slots of removed keys, and compact the node arena and prefix table in the same pass.
+    /// Returns the number of leaf arena bytes reclaimed.
+    ///
+    /// Leaf records are rewritten in key order, so [`HotTree::iter_insertion_order`]
+    /// yields key order for the keys present at this point. Every outstanding [`Handle`]
+    /// is invalidated.
+    pub fn compact_leaves(&mut self) -> usize {
+        let old_len = self.leaves.len();
+        let growth = self.config.growth;
+        let old_nodes = std::mem::replace(&mut self.nodes, NodeArena::new(growth));
+        let mut new_nodes = NodeArena::new(growth);
+        let mut leaves = LeafBytes::default();
+        leaves.reserve(growth, old_len - self.dead_leaf_bytes);
+        let mut values = Vec::with_capacity(if self.config.inline_values {
+            0
+        } else {
+            self.count
+        });
+
+        let mut stamps = Vec::with_capacity(values.capacity());
+
+        let root = self.root;
+        let (new_root, _) = Self::copy_subtree(&old_nodes, root, &mut new_nodes, |leaf| {
+            self.relocate_leaf(leaf, &mut leaves, &mut values, &mut stamps)
+        });
+
+        self.nodes = new_nodes;
+        self.root = new_root;
+        self.leaves = leaves;
+        self.values = values;
+        if let Some(old) = &mut self.change_stamps {
+            *old = stamps;
+        }
+        self.dead_leaf_bytes = 0;
+        self.generation = self.generation.wrapping_add(1);
+        self.leaf_epoch = self.leaf_epoch.wrapping_add(1);
+        self.compact_prefixes();
+        old_len - self.leaves.len()
+    }
+
+    /// [`HotTree::compact_leaves`], then size the leaf arena, node arena and value slots
+    /// to `1 + slack_fraction` times their contents, so that inserts after compaction do
+    /// not immediately regrow them. Returns the number of leaf arena bytes reclaimed.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `slack_fraction` is finite and non-negative.
+    pub fn compact_with_slack(&mut self, slack_fraction: f64) -> usize {
+        assert!(
+            slack_fraction.is_finite() && slack_fraction >= 0.0,
+            "slack fraction must be finite and non-negative, got {slack_fraction}"
+        );
+        let reclaimed = self.compact_leaves();
+        let slack = |len: usize| (len as f64 * slack_fraction).ceil() as usize;
+        self.leaves.shrink_to_fit();
+        self.leaves.reserve_exact(slack(self.leaves.len()));
+        self.nodes.data.shrink_to_fit();
+        self.nodes.data.reserve_exact(slack(self.nodes.data.len()));
+        self.values.shrink_to_fit();
+        self.values.reserve_exact(slack(self.values.len()));
+        if let Some(stamps) = &mut self.change_stamps {
+            stamps.shrink_to_fit();
+            stamps.reserve_exact(slack(stamps.len()));
+        }
+        reclaimed
+    }
+
+    /// Re-derive every live key's prefix from scratch, as if the keys were inserted anew,
+    /// and rebuild the prefix table, leaf arena and node arena to match.
+    ///
+    /// Prefixes chosen by [`HotTree::insert_with_prefix_hint`], or missed because the
+    /// table was full or a hash collided at the time, give way to the delimiter-detected
+    /// prefixes of the keys present now; [`HotTree::compact`] only drops unused prefixes.
+    /// Like [`HotTree::compact_leaves`], this drops removed keys' records, leaves records in
+    /// key order, and invalidates every outstanding [`Handle`].
+    pub fn rebuild_prefixes(&mut self)
+    where
+        S: Clone,
+    {
+        let growth = self.config.growth;
+        let mut fresh = Self::with_hasher(self.hash_builder.clone());
+        fresh.shared_prefixes = self.shared_prefixes.clone();
+        fresh.config = self.config;
+        fresh.count = self.count;
+        fresh.generation = self.generation.wrapping_add(1);
+        fresh.leaf_epoch = self.leaf_epoch.wrapping_add(1);
+        fresh.bloom = self.bloom.take();
+        fresh.change_generation = self.change_generation;
+        let tracked = self.change_stamps.is_some();
+        let mut stamps = Vec::new();
+
+        let old_nodes = std::mem::replace(&mut self.nodes, NodeArena::new(growth));
+        let mut new_nodes = NodeArena::new(growth);
+        let mut key = Vec::new();
+        let (root, _) = Self::copy_subtree(&old_nodes, self.root, &mut new_nodes, |leaf| {
+            let off = leaf.leaf_off();
+            self.get_leaf_key_into(off, &mut key);
+            let new_leaf = fresh.store_leaf(&key, None);
+            if std::mem::size_of::<V>() > 0 && self.config.inline_values {
+                let value = self.take_value(off).expect("reachable leaf has a value");
+                fresh.push_value(new_leaf, value);
+            } else if std::mem::size_of::<V>() > 0 {
+                let idx = self.get_leaf_value_idx(off);
+                fresh.values.push(self.values[idx].take());
+                if let Some(old) = &self.change_stamps {
+                    stamps.push(old[idx]);
+                }
+            }
+            new_leaf
+        });
+
+        fresh.nodes = new_nodes;
+        fresh.root = root;
+        fresh.zst_values = std::mem::take(&mut self.zst_values);
+        fresh.change_stamps = tracked.then_some(stamps);
+        *self = fresh;
+    }
+
+    /// Drop local prefixes with no live references and renumber the rest, rewriting the
+    /// prefix and tail ids of every record in the leaf arena.
+    ///
+    /// Records of removed keys that used a dropped prefix are pointed at the empty prefix;
+    /// only [`HotTree::debug_entries`] still decodes them.
+    fn compact_prefixes(&mut self) {
+        if self.prefix_refs.iter().skip(1).all(|&refs| refs > 0) {
+            return;
+        }
+        let base = self.prefix_id_base();
+        let mut remap = vec![0u16; self.prefix_refs.len()];
+        let mut pool = Vec::new();
+        let mut offsets = vec![0];
+        let mut refs = vec![0];
+        let mut hash = HashMap::new();
+        for (idx, &n) in self.prefix_refs.iter().enumerate().skip(1) {
+            if n == 0 {
+                continue;
+            }
+            let id = (base + offsets.len()) as u16;
+            let prefix = self.get_prefix((base + idx) as u16);
+            offsets.push(pool.len() as u32);
+            pool.extend_from_slice(prefix);
+            refs.push(n);
+            hash.insert(self.hash_bytes(prefix), id);
+            remap[idx] = id;
+        }
+
+        let renumber = |id: u16| {
+            if id as usize > base {
+                remap[id as usize - base]
+            } else {
+                id
+            }
+        };
+        let mut off = 0;
+        while off < self.leaves.len() {
+            let rec = self.leaf_record(off as u64);
+            let len = self.leaf_record_len(off as u64);
+            self.leaves[off..off + 2].copy_from_slice(&renumber(rec.prefix_id).to_le_bytes());
+            if self.config.intern_suffixes {
+                self.leaves[off + 2..off + 4].copy_from_slice(&renumber(rec.tail_id).to_le_bytes());
+            }
+            off += len;
+        }
+
+        pool.shrink_to_fit();
+        offsets.shrink_to_fit();
+        refs.shrink_to_fit();
+        hash.shrink_to_fit();
+        self.prefix_pool = pool;
+        self.prefix_offsets = offsets;
+        self.prefix_refs = refs;
+        self.prefix_hash = hash;
+    }
+
+    /// Live-leaf reference count of a prefix id, or `None` for the empty prefix and ids
+    /// of the shared pool, which are never dropped.
+    fn prefix_refs_mut(&mut self, id: u16) -> Option<&mut u32> {
+        let base = self.prefix_id_base();
+        if id as usize <= base {
+            return None;
+        }
+        self.prefix_refs.get_mut(id as usize - base)
+    }
 
+    /// Append a copy of the live leaf record `leaf` to `leaves`, moving its value into
+    /// `values` (and its change stamp into `stamps`, when tracked) when it has a slot, and
+    /// return the new leaf pointer.
+    fn relocate_leaf(
+        &mut self,
+        leaf: Ptr,
+        leaves: &mut LeafBytes,
+        values: &mut Vec<Option<V>>,
+        stamps: &mut Vec<u64>,
+    ) -> Ptr {
+        let off = leaf.leaf_off();
+        let rec = self.leaf_record(off);
+        let new_off = leaves.len();
+        // Everything up to the value is position-independent.
+        leaves.extend_from_slice(&self.leaves[off as usize..rec.suffix_start + rec.suffix_len]);
+        if std::mem::size_of::<V>() > 0 && self.config.inline_values {
+            let pad = leaves.len().next_multiple_of(std::mem::align_of::<V>()) - leaves.len();
+            leaves.extend_zeroed(pad);
+            let value = self.inline_value_off(off);
+            leaves.extend_from_slice(&self.leaves[value..value + std::mem::size_of::<V>()]);
+        } else if std::mem::size_of::<V>() > 0 {
+            let idx = self.get_leaf_value_idx(off);
+            Self::push_value_idx(leaves, self.config.value_index_width, values.len());
+            values.push(self.values[idx].take());
+            if let Some(old) = &self.change_stamps {
+                stamps.push(old[idx]);
+            }
+        }
+        Ptr::leaf(new_off as u64)
+    }
+
+    /// Copy the subtree at `root` from `old_nodes` into `new_nodes`, passing every leaf
+    /// pointer through `map_leaf`. Returns the new root and the number of nodes written.
+    #[allow(clippy::needless_range_loop)]
+    fn copy_subtree(
+        old_nodes: &NodeArena,
+        root: Ptr,
+        new_nodes: &mut NodeArena,
+        mut map_leaf: impl FnMut(Ptr) -> Ptr,
+    ) -> (Ptr, usize) {
+        if root.is_null() {
+            return (root, 0);
+        }
+        if root.is_leaf() {
+            return (map_leaf(root), 0);
+        }
+
+        let mut rewritten = 0usize;
         #[derive(Clone, Copy)]
         struct Frame {
             old_off: u64,
@@ -1284,7 +2535,7 @@ impl<V> HotTree<V> {
         }
 
         let mut stack: Vec<Frame> = Vec::with_capacity(64);
-        stack.push(load_frame(&old_nodes, self.root, None));
+        stack.push(load_frame(old_nodes, root, None));
 
         let mut new_root = Ptr::NULL;
         while let Some(frame) = stack.last_mut() {
@@ -1292,8 +2543,10 @@ impl<V> HotTree<V> {
                 let idx = frame.next_child;
                 frame.next_child += 1;
                 let child = frame.ptrs[idx];
-                if !child.is_leaf() {
-                    stack.push(load_frame(&old_nodes, child, Some(idx)));
+                if child.is_leaf() {
+                    frame.ptrs[idx] = map_leaf(child);
+                } else {
+                    stack.push(load_frame(old_nodes, child, Some(idx)));
                 }
                 continue;
             }
@@ -1339,26 +2592,30 @@ impl<V> HotTree<V> {
             }
         }
 
-        self.nodes = new_nodes;
-        self.root = new_root;
-        rewritten
+        (new_root, rewritten)
     }
 
-    /// FNV-1a hash for prefix lookup
+    /// Hash for prefix lookup and the Bloom filter
     #[inline]
-    fn hash_prefix(prefix: &[u8]) -> u64 {
-        let mut hash: u64 = 0xcbf29ce484222325;
-        for &byte in prefix {
-            hash ^= byte as u64;
-            hash = hash.wrapping_mul(0x100000001b3);
-        }
-        hash
+    fn hash_bytes(&self, bytes: &[u8]) -> u64 {
+        let mut hasher = self.hash_builder.build_hasher();
+        hasher.write(bytes);
+        hasher.finish()
     }
 
     // =========================================================================
     // Prefix compression
     // =========================================================================
 
+    /// [`HotTree::extract_natural_prefix`], unless the key is too short to be given one.
+    #[inline]
+    fn natural_prefix<'k>(&self, key: &'k [u8]) -> &'k [u8] {
+        if key.len() < self.config.min_key_len_for_prefix {
+            return &[];
+        }
+        Self::extract_natural_prefix(key)
+    }
+
     /// Extract natural prefix from key (up to delimiter)
     fn extract_natural_prefix(key: &[u8]) -> &[u8] {
         if key.len() < MIN_PREFIX_LEN {
@@ -1377,9 +2634,23 @@ impl<V> HotTree<V> {
         &[]
     }
 
+    /// Number of ids taken by the shared prefix pool; local prefix ids start after it.
+    #[inline]
+    fn prefix_id_base(&self) -> usize {
+        self.shared_prefixes.as_ref().map_or(0, |pool| pool.len())
+    }
+
+    #[inline]
+    fn shared_prefix_id(&self, prefix: &[u8]) -> Option<u16> {
+        self.shared_prefixes.as_ref()?.id(prefix)
+    }
+
     /// Register a prefix, returns its ID
     fn register_prefix(&mut self, prefix: &[u8]) -> u16 {
-        let hash = Self::hash_prefix(prefix);
+        if let Some(id) = self.shared_prefix_id(prefix) {
+            return id;
+        }
+        let hash = self.hash_bytes(prefix);
 
         // Check if already exists
         if let Some(&id) = self.prefix_hash.get(&hash) {
@@ -1392,15 +2663,21 @@ impl<V> HotTree<V> {
             return 0;
         }
 
-        if self.prefix_offsets.len() >= MAX_PREFIXES {
+        let base = self.prefix_id_base();
+        if self.prefix_offsets.len() + base >= MAX_PREFIXES {
             return 0; // Fall back to empty prefix
         }
 
-        let id = self.prefix_offsets.len() as u16;
+        // Local index 0 is the empty prefix, so local prefixes start at `base + 1`.
+        let id = (self.prefix_offsets.len() + base) as u16;
 
         // Store offset and prefix bytes
         let offset = self.prefix_pool.len() as u32;
+        GrowthPolicy::Doubling.reserve(&mut self.prefix_offsets, 1);
         self.prefix_offsets.push(offset);
+        GrowthPolicy::Doubling.reserve(&mut self.prefix_refs, 1);
+        self.prefix_refs.push(0);
+        GrowthPolicy::Doubling.reserve(&mut self.prefix_pool, prefix.len());
         self.prefix_pool.extend_from_slice(prefix);
 
         self.prefix_hash.insert(hash, id);
@@ -1409,12 +2686,15 @@ impl<V> HotTree<V> {
 
     /// Get or create prefix for a key
     fn get_or_create_prefix(&mut self, key: &[u8]) -> (u16, usize) {
-        let natural = Self::extract_natural_prefix(key);
+        let natural = self.natural_prefix(key);
         if natural.is_empty() {
             return (0, 0); // Empty prefix
         }
+        if let Some(id) = self.shared_prefix_id(natural) {
+            return (id, natural.len());
+        }
 
-        let hash = Self::hash_prefix(natural);
+        let hash = self.hash_bytes(natural);
 
         // Check if prefix exists
         if let Some(&id) = self.prefix_hash.get(&hash) {
@@ -1431,10 +2711,60 @@ impl<V> HotTree<V> {
         (id, if id == 0 { 0 } else { natural.len() })
     }
 
+    /// Get or create the caller-chosen `prefix`, falling back to the empty prefix where
+    /// [`HotTree::register_prefix`] does.
+    fn get_or_create_hinted_prefix(&mut self, prefix: &[u8]) -> (u16, usize) {
+        if prefix.is_empty() {
+            return (0, 0);
+        }
+        let id = self.register_prefix(prefix);
+        (id, if id == 0 { 0 } else { prefix.len() })
+    }
+
+    /// Natural tail: from the last delimiter to the end of the key, not overlapping the
+    /// first `start` bytes.
+    fn extract_natural_tail(key: &[u8], start: usize) -> &[u8] {
+        if key.len() < start + MIN_PREFIX_LEN {
+            return &[];
+        }
+
+        let lo = start.max(key.len().saturating_sub(MAX_PREFIX_LEN));
+        for i in (lo..=key.len() - MIN_PREFIX_LEN).rev() {
+            let b = key[i];
+            if b == b'/' || b == b':' || b == b'\\' {
+                return &key[i..]; // Include delimiter
+            }
+        }
+        &[]
+    }
+
+    /// Intern the natural tail of `key[start..]` in the prefix pool once it recurs.
+    /// Returns (tail_id, tail_len), or (0, 0) if the key has no tail or it stays inline.
+    fn get_or_create_tail(&mut self, key: &[u8], start: usize) -> (u16, usize) {
+        let tail = Self::extract_natural_tail(key, start);
+        if tail.is_empty() {
+            return (0, 0);
+        }
+        let hash = self.hash_bytes(tail);
+        let known = self.shared_prefix_id(tail).is_some() || self.prefix_hash.contains_key(&hash);
+        if !known && !self.tail_sketch.record(hash) {
+            return (0, 0);
+        }
+        let id = self.register_prefix(tail);
+        (id, if id == 0 { 0 } else { tail.len() })
+    }
+
     /// Get prefix bytes for a prefix ID (O(1) lookup)
     #[inline]
     fn get_prefix(&self, id: u16) -> &[u8] {
-        let idx = id as usize;
+        let base = self.prefix_id_base();
+        let mut idx = id as usize;
+        if idx > 0 {
+            if let Some(pool) = self.shared_prefixes.as_ref().filter(|_| idx <= base) {
+                return pool.get(id);
+            }
+            idx -= base;
+        }
         if idx >= self.prefix_offsets.len() {
             return &[];
         }
@@ -1454,16 +2784,50 @@ impl<V> HotTree<V> {
     // =========================================================================
 
     /// Store leaf with prefix compression
-    /// Format: [prefix_id:2][suffix_len:1-3][suffix...][value_idx:4]
+    /// Format: [prefix_id:2][suffix_len:1-3][suffix...][value_idx:2|4]
+    /// With suffix interning: [prefix_id:2][tail_id:2][suffix_len:1-3][suffix...][value_idx:2|4],
+    /// where the inline suffix excludes the interned tail.
+    ///
+    /// With inline values, value_idx is replaced by zeroed space for a `V` at the next
+    /// offset aligned for `V`, filled in by [`HotTree::push_value`].
     ///
     /// suffix_len encoding:
     /// - If < 255: [len:1]
     /// - If >= 255: [0xFF][len:2]
     ///
     /// Returns a leaf pointer (byte offset into `leaves`).
-    fn store_leaf(&mut self, key: &[u8]) -> Ptr {
-        let (prefix_id, prefix_len) = self.get_or_create_prefix(key);
-        let suffix = &key[prefix_len..];
+    ///
+    /// `prefix_hint` of `Some(len)` takes `key[..len]` as the prefix instead of detecting one.
+    fn store_leaf(&mut self, key: &[u8], prefix_hint: Option<usize>) -> Ptr {
+        if self.config.fixed_key_len.is_some() {
+            return self.store_fixed_leaf(key);
+        }
+        let (prefix_id, prefix_len) = match prefix_hint {
+            Some(len) => self.get_or_create_hinted_prefix(&key[..len]),
+            None => self.get_or_create_prefix(key),
+        };
+        let (tail_id, tail_len) = if self.config.intern_suffixes {
+            self.get_or_create_tail(key, prefix_len)
+        } else {
+            (0, 0)
+        };
+        for id in [prefix_id, tail_id] {
+            if let Some(refs) = self.prefix_refs_mut(id) {
+                *refs += 1;
+            }
+        }
+        let suffix = &key[prefix_len..key.len() - tail_len];
+        let value_bytes = if std::mem::size_of::<V>() == 0 {
+            0
+        } else if self.config.inline_values {
+            std::mem::align_of::<V>() - 1 + std::mem::size_of::<V>()
+        } else {
+            self.config.value_index_width.bytes()
+        };
+        let tail_id_bytes = if self.config.intern_suffixes { 2 } else { 0 };
+        let slen_bytes = if suffix.len() < 255 { 1 } else { 3 };
+        let record_len = 2 + tail_id_bytes + slen_bytes + suffix.len() + value_bytes;
+        self.leaves.reserve(self.config.growth, record_len);
 
         // Record byte offset and create leaf pointer (38-bit offset)
         let byte_offset = self.leaves.len() as u64;
@@ -1478,6 +2842,9 @@ impl<V> HotTree<V> {
 
         // Store prefix_id (2 bytes)
         self.leaves.extend_from_slice(&prefix_id.to_le_bytes());
+        if self.config.intern_suffixes {
+            self.leaves.extend_from_slice(&tail_id.to_le_bytes());
+        }
 
         // Store suffix_len (variable length - 1 byte for < 255, 3 bytes for >= 255)
         let suffix_len = suffix.len();
@@ -1492,15 +2859,65 @@ impl<V> HotTree<V> {
         // Store suffix
         self.leaves.extend_from_slice(suffix);
 
-        // Store value_idx (4 bytes) if not ZST
-        if std::mem::size_of::<V>() > 0 {
-            let value_idx = self.values.len() as u32;
-            self.leaves.extend_from_slice(&value_idx.to_le_bytes());
+        // Store value_idx (2 or 4 bytes) if not ZST; inline values are written by the caller
+        if std::mem::size_of::<V>() > 0 && self.config.inline_values {
+            let pad = self
+                .leaves
+                .len()
+                .next_multiple_of(std::mem::align_of::<V>())
+                - self.leaves.len();
+            self.leaves.extend_zeroed(pad + std::mem::size_of::<V>());
+        } else if std::mem::size_of::<V>() > 0 {
+            let value_idx = self.values.len();
+            Self::push_value_idx(&mut self.leaves, self.config.value_index_width, value_idx);
         }
 
         leaf_ptr
     }
 
+    /// Store a leaf of a fixed-length tree: `[key:N][value_idx:2|4]`, or the aligned value
+    /// with inline values.
+    fn store_fixed_leaf(&mut self, key: &[u8]) -> Ptr {
+        debug_assert_eq!(Some(key.len()), self.config.fixed_key_len);
+        let value_bytes = if std::mem::size_of::<V>() == 0 {
+            0
+        } else if self.config.inline_values {
+            std::mem::align_of::<V>() - 1 + std::mem::size_of::<V>()
+        } else {
+            self.config.value_index_width.bytes()
+        };
+        self.leaves
+            .reserve(self.config.growth, key.len() + value_bytes);
+        let byte_offset = self.leaves.len() as u64;
+        assert!(
+            byte_offset <= Ptr::OFFSET_MASK,
+            "LEAF ARENA OVERFLOW: leaves.len()={byte_offset} exceeds max offset {}",
+            Ptr::OFFSET_MASK
+        );
+
+        self.leaves.extend_from_slice(key);
+        if std::mem::size_of::<V>() > 0 && self.config.inline_values {
+            let pad = self
+                .leaves
+                .len()
+                .next_multiple_of(std::mem::align_of::<V>())
+                - self.leaves.len();
+            self.leaves.extend_zeroed(pad + std::mem::size_of::<V>());
+        } else if std::mem::size_of::<V>() > 0 {
+            let value_idx = self.values.len();
+            Self::push_value_idx(&mut self.leaves, self.config.value_index_width, value_idx);
+        }
+        Ptr::leaf(byte_offset)
+    }
+
+    fn push_value_idx(leaves: &mut LeafBytes, width: ValueIndexWidth, value_idx: usize) {
+        debug_assert!(value_idx < width.max_slots());
+        match width {
+            ValueIndexWidth::U16 => leaves.extend_from_slice(&(value_idx as u16).to_le_bytes()),
+            ValueIndexWidth::U32 => leaves.extend_from_slice(&(value_idx as u32).to_le_bytes()),
+        }
+    }
+
     /// Read suffix_len and return (suffix_len, bytes_consumed_for_header)
     #[inline]
     fn read_suffix_len(&self, off: usize) -> (usize, usize) {
@@ -1513,127 +2930,249 @@ impl<V> HotTree<V> {
         }
     }
 
-    /// Reconstruct full key from a leaf offset.
-    fn get_leaf_key(&self, leaf_off: u64) -> Vec<u8> {
-        let o = leaf_off as usize;
+    /// Parse the header of the leaf record at `leaf_off`.
+    #[inline]
+    fn leaf_record(&self, leaf_off: u64) -> LeafRecord {
+        let mut o = leaf_off as usize;
+        if let Some(len) = self.config.fixed_key_len {
+            return LeafRecord {
+                prefix_id: 0,
+                tail_id: 0,
+                suffix_start: o,
+                suffix_len: len,
+            };
+        }
         let prefix_id = u16::from_le_bytes([self.leaves[o], self.leaves[o + 1]]);
-        let (suffix_len, slen_bytes) = self.read_suffix_len(o + 2);
-        let suffix_start = o + 2 + slen_bytes;
-        let suffix = &self.leaves[suffix_start..suffix_start + suffix_len];
-
-        let prefix = self.get_prefix(prefix_id);
-        let mut key = Vec::with_capacity(prefix.len() + suffix.len());
-        key.extend_from_slice(prefix);
-        key.extend_from_slice(suffix);
-        key
+        o += 2;
+        let mut tail_id = 0;
+        if self.config.intern_suffixes {
+            tail_id = u16::from_le_bytes([self.leaves[o], self.leaves[o + 1]]);
+            o += 2;
+        }
+        let (suffix_len, slen_bytes) = self.read_suffix_len(o);
+        LeafRecord {
+            prefix_id,
+            tail_id,
+            suffix_start: o + slen_bytes,
+            suffix_len,
+        }
     }
 
-    fn get_leaf_value_idx(&self, leaf_off: u64) -> usize {
-        debug_assert_ne!(std::mem::size_of::<V>(), 0);
-        let o = leaf_off as usize;
-        let (suffix_len, slen_bytes) = self.read_suffix_len(o + 2);
-        let val_off = o + 2 + slen_bytes + suffix_len;
-        u32::from_le_bytes([
-            self.leaves[val_off],
-            self.leaves[val_off + 1],
-            self.leaves[val_off + 2],
-            self.leaves[val_off + 3],
-        ]) as usize
+    #[inline]
+    fn leaf_suffix(&self, rec: LeafRecord) -> &[u8] {
+        &self.leaves[rec.suffix_start..rec.suffix_start + rec.suffix_len]
     }
 
+    /// Pieces of a leaf's key.
     #[inline]
-    fn leaf_key_equals(&self, leaf_off: u64, key: &[u8]) -> bool {
-        let o = leaf_off as usize;
-        let prefix_id = u16::from_le_bytes([self.leaves[o], self.leaves[o + 1]]);
-        let (suffix_len, slen_bytes) = self.read_suffix_len(o + 2);
-        let suffix_start = o + 2 + slen_bytes;
-
-        let prefix = self.get_prefix(prefix_id);
-        if key.len() != prefix.len() + suffix_len {
-            return false;
-        }
-        if !key.starts_with(prefix) {
-            return false;
+    fn leaf_key(&self, leaf_off: u64) -> LeafKey<'_> {
+        let rec = self.leaf_record(leaf_off);
+        LeafKey {
+            prefix: self.get_prefix(rec.prefix_id),
+            mid: self.leaf_suffix(rec),
+            tail: self.get_prefix(rec.tail_id),
         }
-        let key_suffix = &key[prefix.len()..];
-        let leaf_suffix = &self.leaves[suffix_start..suffix_start + suffix_len];
-        key_suffix == leaf_suffix
     }
 
-    fn first_diff_bit_leaf(&self, leaf_off: u64, other: &[u8]) -> Option<u16> {
-        let o = leaf_off as usize;
-        let prefix_id = u16::from_le_bytes([self.leaves[o], self.leaves[o + 1]]);
-        let (suffix_len, slen_bytes) = self.read_suffix_len(o + 2);
-        let suffix_start = o + 2 + slen_bytes;
-
-        let prefix = self.get_prefix(prefix_id);
-        let suffix = &self.leaves[suffix_start..suffix_start + suffix_len];
-        let leaf_len = prefix.len() + suffix.len();
-        let max_len = leaf_len.max(other.len());
-
-        for i in 0..max_len {
-            let ab = if i < prefix.len() {
-                prefix[i]
-            } else if i < leaf_len {
-                suffix[i - prefix.len()]
-            } else {
-                0
-            };
-            let bb = other.get(i).copied().unwrap_or(0);
-            if ab != bb {
-                let xor = ab ^ bb;
-                return Some(i as u16 * 8 + xor.leading_zeros() as u16);
-            }
-        }
-        None
+    /// Reconstruct full key from a leaf offset.
+    fn get_leaf_key(&self, leaf_off: u64) -> Vec<u8> {
+        let mut key = Vec::new();
+        self.get_leaf_key_into(leaf_off, &mut key);
+        key
     }
 
-    #[inline]
-    fn zst_value_ref(&self) -> &V {
-        debug_assert_eq!(std::mem::size_of::<V>(), 0);
-        // SAFETY: for ZSTs, any non-null properly aligned pointer is a valid reference.
-        unsafe { &*std::ptr::NonNull::<V>::dangling().as_ptr() }
+    /// Reconstruct the key of a leaf into `buf`, replacing its contents.
+    fn get_leaf_key_into(&self, leaf_off: u64, buf: &mut Vec<u8>) {
+        buf.clear();
+        self.leaf_key(leaf_off).extend_into(buf);
     }
 
-    #[inline]
-    fn bit_at_leaf(&self, leaf_off: u64, pos: u16) -> u8 {
-        let byte_idx = (pos / 8) as usize;
-        let bit_idx = 7 - (pos % 8);
-
-        let o = leaf_off as usize;
-        let prefix_id = u16::from_le_bytes([self.leaves[o], self.leaves[o + 1]]);
-        let (suffix_len, slen_bytes) = self.read_suffix_len(o + 2);
-        let suffix_start = o + 2 + slen_bytes;
-
-        let prefix = self.get_prefix(prefix_id);
-        let leaf_len = prefix.len() + suffix_len;
-        let byte = if byte_idx < prefix.len() {
-            prefix[byte_idx]
-        } else if byte_idx < leaf_len {
-            self.leaves[suffix_start + (byte_idx - prefix.len())]
+    /// Total bytes of the leaf record at `leaf_off`, including its value index.
+    fn leaf_record_len(&self, leaf_off: u64) -> usize {
+        let rec = self.leaf_record(leaf_off);
+        let end = if std::mem::size_of::<V>() == 0 {
+            rec.suffix_start + rec.suffix_len
+        } else if self.config.inline_values {
+            self.inline_value_off(leaf_off) + std::mem::size_of::<V>()
         } else {
-            0
+            rec.suffix_start + rec.suffix_len + self.config.value_index_width.bytes()
         };
-
-        (byte >> bit_idx) & 1
+        end - leaf_off as usize
     }
 
-    // =========================================================================
-    // Generic node operations (TwoEntries / HOT)
-    // =========================================================================
-
+    /// Offset of the inline value in the leaf record at `leaf_off`.
     #[inline]
-    fn node_entry_count(&self, node_off: u64) -> usize {
-        self.nodes.n(node_off)
+    fn inline_value_off(&self, leaf_off: u64) -> usize {
+        debug_assert!(self.config.inline_values);
+        let rec = self.leaf_record(leaf_off);
+        (rec.suffix_start + rec.suffix_len).next_multiple_of(std::mem::align_of::<V>())
     }
 
     #[inline]
-    fn node_most_significant_bit(&self, node_off: u64) -> u16 {
-        match self.nodes.tag(node_off) {
-            NODE_TWO_ENTRIES => self.nodes.two_entries_disc(node_off),
-            tag if hot_is_hot_node(tag) => self.nodes.hot_mapping(node_off).most_significant_bit(),
-            other => panic!("unknown node tag {other}"),
-        }
+    fn inline_value_ptr(&self, leaf_off: u64) -> *const V {
+        self.leaves[self.inline_value_off(leaf_off)..]
+            .as_ptr()
+            .cast::<V>()
+    }
+
+    #[inline]
+    fn inline_value_mut_ptr(&mut self, leaf_off: u64) -> *mut V {
+        let off = self.inline_value_off(leaf_off);
+        self.leaves[off..].as_mut_ptr().cast::<V>()
+    }
+
+    /// Store the value for the freshly stored leaf `leaf`.
+    fn push_value(&mut self, leaf: Ptr, value: V) {
+        if std::mem::size_of::<V>() == 0 {
+            self.zst_values.push(value);
+        } else if self.config.inline_values {
+            // SAFETY: `inline_values` is only set for `V: InlineValue`, and `store_leaf`
+            // reserved `size_of::<V>()` bytes at this `V`-aligned offset of the word-aligned
+            // leaf arena.
+            unsafe { self.inline_value_mut_ptr(leaf.leaf_off()).write(value) };
+        } else {
+            GrowthPolicy::Doubling.reserve(&mut self.values, 1);
+            self.values.push(Some(value));
+            self.stamp_slot(self.values.len() - 1);
+        }
+    }
+
+    /// Stamp value slot `idx` (possibly just pushed) with the next change generation, if
+    /// tracking changes.
+    fn stamp_slot(&mut self, idx: usize) {
+        let Some(stamps) = &mut self.change_stamps else {
+            return;
+        };
+        if idx == stamps.len() {
+            GrowthPolicy::Doubling.reserve(stamps, 1);
+            stamps.push(self.change_generation);
+        } else {
+            stamps[idx] = self.change_generation;
+        }
+        self.change_generation += 1;
+    }
+
+    /// Replace the value of the live sized-value leaf at `leaf_off`.
+    fn replace_value(&mut self, leaf_off: u64, value: V) -> Option<V> {
+        debug_assert_ne!(std::mem::size_of::<V>(), 0);
+        if self.config.inline_values {
+            // SAFETY: see `push_value`; the slot holds a `V` written there by it.
+            return Some(unsafe { self.inline_value_mut_ptr(leaf_off).replace(value) });
+        }
+        let idx = self.get_leaf_value_idx(leaf_off);
+        self.stamp_slot(idx);
+        self.values[idx].replace(value)
+    }
+
+    /// Move the value out of the sized-value leaf at `leaf_off`, which is being unlinked.
+    fn take_value(&mut self, leaf_off: u64) -> Option<V> {
+        debug_assert_ne!(std::mem::size_of::<V>(), 0);
+        if self.config.inline_values {
+            // SAFETY: see `push_value`; inline values are `Copy`, so the dead record may keep
+            // its bytes.
+            return Some(unsafe { self.inline_value_ptr(leaf_off).read() });
+        }
+        let idx = self.get_leaf_value_idx(leaf_off);
+        self.values[idx].take()
+    }
+
+    fn get_leaf_value_idx(&self, leaf_off: u64) -> usize {
+        debug_assert_ne!(std::mem::size_of::<V>(), 0);
+        let rec = self.leaf_record(leaf_off);
+        let o = rec.suffix_start + rec.suffix_len;
+        match self.config.value_index_width {
+            ValueIndexWidth::U16 => {
+                u16::from_le_bytes([self.leaves[o], self.leaves[o + 1]]) as usize
+            }
+            ValueIndexWidth::U32 => u32::from_le_bytes([
+                self.leaves[o],
+                self.leaves[o + 1],
+                self.leaves[o + 2],
+                self.leaves[o + 3],
+            ]) as usize,
+        }
+    }
+
+    /// Value of a live leaf, or `None` for tombstoned/vacated leaves.
+    #[inline]
+    fn leaf_value(&self, ptr: Ptr) -> Option<&V> {
+        if std::mem::size_of::<V>() == 0 {
+            return (!ptr.is_tombstone()).then(|| self.zst_value_ref());
+        }
+        if self.config.inline_values {
+            // SAFETY: see `push_value`.
+            return Some(unsafe { &*self.inline_value_ptr(ptr.leaf_off()) });
+        }
+        let idx = self.get_leaf_value_idx(ptr.leaf_off());
+        self.values[idx].as_ref()
+    }
+
+    fn leaf_value_mut(&mut self, ptr: Ptr) -> Option<&mut V> {
+        if std::mem::size_of::<V>() == 0 {
+            return self.zst_values.last_mut();
+        }
+        if self.config.inline_values {
+            // SAFETY: see `push_value`.
+            return Some(unsafe { &mut *self.inline_value_mut_ptr(ptr.leaf_off()) });
+        }
+        let idx = self.get_leaf_value_idx(ptr.leaf_off());
+        self.stamp_slot(idx);
+        self.values[idx].as_mut()
+    }
+
+    /// Full comparison of a leaf's key against `key`.
+    ///
+    /// This cannot be narrowed to the bytes below the last discriminative bit seen while
+    /// descending: the descent only tests discriminative bits, so a missing key can reach
+    /// a leaf that differs from it anywhere else, including well above that bit.
+    #[inline]
+    fn leaf_key_equals(&self, leaf_off: u64, key: &[u8]) -> bool {
+        self.leaf_key(leaf_off).equals(key)
+    }
+
+    fn first_diff_bit_leaf(&self, leaf_off: u64, other: &[u8]) -> Option<u16> {
+        let leaf = self.leaf_key(leaf_off);
+        first_diff_bit_by(
+            leaf.len(),
+            other.len(),
+            |i| leaf.byte(i),
+            |i| key_byte(other, i),
+        )
+    }
+
+    #[inline]
+    fn zst_value_ref(&self) -> &V {
+        debug_assert_eq!(std::mem::size_of::<V>(), 0);
+        // SAFETY: for ZSTs, any non-null properly aligned pointer is a valid reference.
+        unsafe { &*std::ptr::NonNull::<V>::dangling().as_ptr() }
+    }
+
+    #[inline]
+    fn bit_at_leaf(&self, leaf_off: u64, pos: u16) -> u8 {
+        let byte_idx = (pos / 8) as usize;
+        let bit_idx = 7 - (pos % 8);
+
+        let byte = self.leaf_key(leaf_off).byte(byte_idx);
+
+        (byte >> bit_idx) & 1
+    }
+
+    // =========================================================================
+    // Generic node operations (TwoEntries / HOT)
+    // =========================================================================
+
+    #[inline]
+    fn node_entry_count(&self, node_off: u64) -> usize {
+        self.nodes.n(node_off)
+    }
+
+    #[inline]
+    fn node_most_significant_bit(&self, node_off: u64) -> u16 {
+        match self.nodes.tag(node_off) {
+            NODE_TWO_ENTRIES => self.nodes.two_entries_disc(node_off),
+            tag if hot_is_hot_node(tag) => self.nodes.hot_mapping(node_off).most_significant_bit(),
+            other => panic!("unknown node tag {other}"),
+        }
     }
 
     #[inline]
@@ -1660,11 +3199,11 @@ impl<V> HotTree<V> {
     fn hot_search_mask(&self, node_off: u64, dense_key: u32) -> u32 {
         let tag = self.nodes.tag(node_off);
         let n = self.node_entry_count(node_off);
-        debug_assert!(n >= 2 && n <= 32);
+        debug_assert!((2..=32).contains(&n));
 
         #[cfg(target_arch = "x86_64")]
         {
-            if std::is_x86_feature_detected!("avx2") {
+            if self.config.simd.avx2 {
                 let pk_size = hot_partial_key_size(tag);
                 let pk_base = node_off as usize + NODE_HEADER_SIZE + hot_mapping_size(tag);
                 let pk_bytes = &self.nodes.data[pk_base..pk_base + pk_size * n];
@@ -1674,7 +3213,7 @@ impl<V> HotTree<V> {
                     1 => {
                         let mut buf = [0u8; 32];
                         buf[..n].copy_from_slice(pk_bytes);
-                        // SAFETY: guarded by `is_x86_feature_detected!("avx2")`.
+                        // SAFETY: `SimdSupport` only reports features detected at runtime.
                         unsafe { Self::hot_search_mask_avx2_u8(&buf, dense_key as u8) }
                     }
                     2 => {
@@ -1687,7 +3226,7 @@ impl<V> HotTree<V> {
                             )
                         };
                         buf_bytes[..pk_bytes.len()].copy_from_slice(pk_bytes);
-                        // SAFETY: guarded by `is_x86_feature_detected!("avx2")`.
+                        // SAFETY: `SimdSupport` only reports features detected at runtime.
                         unsafe { Self::hot_search_mask_avx2_u16(&buf, dense_key as u16) }
                     }
                     4 => {
@@ -1700,7 +3239,7 @@ impl<V> HotTree<V> {
                             )
                         };
                         buf_bytes[..pk_bytes.len()].copy_from_slice(pk_bytes);
-                        // SAFETY: guarded by `is_x86_feature_detected!("avx2")`.
+                        // SAFETY: `SimdSupport` only reports features detected at runtime.
                         unsafe { Self::hot_search_mask_avx2_u32(&buf, dense_key) }
                     }
                     _ => 0,
@@ -1795,7 +3334,7 @@ impl<V> HotTree<V> {
             }
             tag if hot_is_hot_node(tag) => {
                 let mapping = self.nodes.hot_mapping(node_off);
-                let dense = mapping.extract_u32(key);
+                let dense = mapping.extract_u32(key, self.config.simd);
                 let mask = self.hot_search_mask(node_off, dense);
                 debug_assert_ne!(mask, 0);
                 let idx = 31u32.saturating_sub(mask.leading_zeros());
@@ -1819,178 +3358,777 @@ impl<V> HotTree<V> {
     fn bit_at(key: &[u8], pos: u16) -> u8 {
         let byte_idx = (pos / 8) as usize;
         let bit_idx = 7 - (pos % 8);
-        if byte_idx < key.len() {
-            (key[byte_idx] >> bit_idx) & 1
-        } else {
-            0
-        }
+        (key_byte(key, byte_idx) >> bit_idx) & 1
     }
 }
 
-impl<V> HotTree<V> {
-    pub fn get(&self, key: &[u8]) -> Option<&V> {
+impl<V, S: BuildHasher> HotTree<V, S> {
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        self.leaf_value(self.find_leaf(key.as_ref())?)
+    }
+
+    /// [`HotTree::get`] that bounds-checks every node, leaf record and value slot it
+    /// touches, returning [`Corruption`] instead of panicking when the arenas are
+    /// inconsistent.
+    ///
+    /// Use it on trees whose arenas may not have been produced by this crate (e.g. mapped
+    /// from an untrusted file). It is slower than `get`, which trusts the structure.
+    pub fn try_get(&self, key: impl AsRef<[u8]>) -> Result<Option<&V>, Corruption> {
+        let key = key.as_ref();
         if self.root.is_null() {
-            return None;
+            return Ok(None);
+        }
+        if let Some(bloom) = &self.bloom {
+            if !bloom.may_contain(self.hash_bytes(key)) {
+                return Ok(None);
+            }
         }
 
         let mut current = self.root;
-
-        loop {
+        // Every step descends at least one level of a tree at most `u8::MAX` high.
+        for _ in 0..=u8::MAX as usize {
+            if current.is_null() {
+                return Err(Corruption::Descent);
+            }
             if current.is_leaf() {
-                let leaf_off = current.leaf_off();
-                if self.leaf_key_equals(leaf_off, key) {
-                    if std::mem::size_of::<V>() == 0 {
-                        return (!current.is_tombstone()).then(|| self.zst_value_ref());
+                let off = current.leaf_off();
+                if !self.leaf_in_bounds(off) {
+                    return Err(Corruption::Leaf { off });
+                }
+                if !self.leaf_key_equals(off, key) {
+                    return Ok(None);
+                }
+                if std::mem::size_of::<V>() != 0 && !self.config.inline_values {
+                    let idx = self.get_leaf_value_idx(off);
+                    if idx >= self.values.len() {
+                        return Err(Corruption::ValueIndex { idx });
                     }
-                    let idx = self.get_leaf_value_idx(leaf_off);
-                    return self.values[idx].as_ref();
                 }
-                return None;
+                return Ok(self.leaf_value(current));
             }
 
-            current = self.node_descend(current.node_off(), key);
+            let off = current.node_off();
+            if !self.node_in_bounds(off) {
+                return Err(Corruption::Node { off });
+            }
+            let idx = match self.nodes.tag(off) {
+                NODE_TWO_ENTRIES => {
+                    usize::from(Self::bit_at(key, self.nodes.two_entries_disc(off)))
+                }
+                _ => {
+                    let dense = self
+                        .nodes
+                        .hot_mapping(off)
+                        .extract_u32(key, self.config.simd);
+                    let mask = self.hot_search_mask(off, dense);
+                    if mask == 0 {
+                        return Err(Corruption::Descent);
+                    }
+                    31 - mask.leading_zeros() as usize
+                }
+            };
+            current = self.node_entry_ptr(off, idx);
         }
+        Err(Corruption::Descent)
     }
 
-    pub fn contains_key(&self, key: &[u8]) -> bool {
-        self.get(key).is_some()
+    /// Whether the node at `off` has a known tag and entry count and lies inside the node
+    /// arena.
+    fn node_in_bounds(&self, off: u64) -> bool {
+        let start = off as usize;
+        let Some(header) = self.nodes.data.get(start..start + NODE_HEADER_SIZE) else {
+            return false;
+        };
+        let (tag, n) = (header[0], header[1] as usize);
+        let size = match tag {
+            NODE_TWO_ENTRIES if n == 2 => NODE_HEADER_SIZE + 2 + 2 * PTR_SIZE,
+            t if hot_is_hot_node(t) && (2..=MAX_COMPOUND_ENTRIES).contains(&n) => {
+                hot_node_size(t, n)
+            }
+            _ => return false,
+        };
+        start + size <= self.nodes.data.len()
     }
 
-    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
-        if self.root.is_null() {
-            let leaf_ptr = self.store_leaf(key);
-            if std::mem::size_of::<V>() == 0 {
-                self.zst_values.push(value);
-            } else {
-                self.values.push(Some(value));
+    /// Whether the whole leaf record at `off`, value bytes included, lies inside the leaf
+    /// arena.
+    fn leaf_in_bounds(&self, off: u64) -> bool {
+        let len = self.leaves.len();
+        let mut end = off as usize;
+        if let Some(key_len) = self.config.fixed_key_len {
+            end += key_len;
+        } else {
+            end += if self.config.intern_suffixes { 4 } else { 2 };
+            if end >= len {
+                return false;
             }
-            self.root = leaf_ptr;
-            self.count += 1;
-            return None;
+            let slen_bytes = if self.leaves[end] < 255 { 1 } else { 3 };
+            if end + slen_bytes > len {
+                return false;
+            }
+            end += slen_bytes + self.read_suffix_len(end).0;
+        }
+        if std::mem::size_of::<V>() == 0 {
+            // Nothing follows the key.
+        } else if self.config.inline_values {
+            end = end.next_multiple_of(std::mem::align_of::<V>()) + std::mem::size_of::<V>();
+        } else {
+            end += self.config.value_index_width.bytes();
         }
+        end <= len
+    }
 
-        if self.root.is_leaf() {
-            let leaf_off = self.root.leaf_off();
-            if self.leaf_key_equals(leaf_off, key) {
-                if std::mem::size_of::<V>() == 0 {
-                    if self.root.is_tombstone() {
-                        self.root = self.root.without_tombstone();
-                        self.zst_values.push(value);
-                        self.count += 1;
-                        return None;
-                    }
-                    let old = self
-                        .zst_values
-                        .pop()
-                        .expect("ZST values must track live key count");
-                    self.zst_values.push(value);
-                    return Some(old);
-                }
+    /// Look up a batch of keys, returning the present ones in ascending key order.
+    ///
+    /// Duplicate input keys are reported once. Sorts the batch first, so this costs
+    /// O(k log k) key comparisons plus one descent per distinct key.
+    pub fn get_sorted_batch(&self, keys: &[&[u8]]) -> Vec<(Vec<u8>, &V)> {
+        let mut sorted = keys.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        sorted
+            .into_iter()
+            .filter_map(|key| Some((key.to_vec(), self.get(key)?)))
+            .collect()
+    }
 
-                let idx = self.get_leaf_value_idx(leaf_off);
-                let old = self.values[idx].replace(value);
-                if old.is_none() {
-                    self.count += 1;
-                }
-                return old;
-            }
+    /// Look up each of `keys` in turn, passing it and its value to `f`, so batch results
+    /// can be streamed out without collecting them.
+    pub fn get_each<'a>(
+        &'a self,
+        keys: impl IntoIterator<Item = &'a [u8]>,
+        mut f: impl FnMut(&'a [u8], Option<&'a V>),
+    ) {
+        for key in keys {
+            f(key, self.get(key));
+        }
+    }
 
-            let diff_bit = self
-                .first_diff_bit_leaf(leaf_off, key)
-                .expect("non-equal keys must have a first differing bit");
+    /// Mutable access to the value for `key`, without rewriting its leaf.
+    pub fn get_mut(&mut self, key: impl AsRef<[u8]>) -> Option<&mut V> {
+        let leaf = self.find_leaf(key.as_ref())?;
+        self.leaf_value_mut(leaf)
+    }
 
-            let new_leaf = self.store_leaf(key);
-            if std::mem::size_of::<V>() == 0 {
-                self.zst_values.push(value);
-            } else {
-                self.values.push(Some(value));
-            }
-            self.count += 1;
+    /// Keep a Bloom filter of the keys so that lookups of most absent keys return without
+    /// descending the trie.
+    ///
+    /// The filter is sized for `expected_keys` keys at a false-positive rate of `fp_rate`,
+    /// and filled with the keys already present. It costs about 10 bits per key at 1%, plus
+    /// a hash of the key on every insert and lookup. Removed keys stay in the filter, so
+    /// after heavy churn it rejects fewer absent keys; call this again to rebuild it.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < fp_rate < 1`.
+    pub fn enable_bloom(&mut self, expected_keys: usize, fp_rate: f64) {
+        let mut bloom = BloomFilter::new(expected_keys.max(self.count), fp_rate);
+        for (key, _) in self.iter() {
+            bloom.insert(self.hash_bytes(&key));
+        }
+        self.bloom = Some(bloom);
+    }
 
-            let new_bit = Self::bit_at(key, diff_bit);
-            let existing_bit = self.bit_at_leaf(leaf_off, diff_bit);
-            debug_assert_ne!(new_bit, existing_bit);
+    /// Size of the Bloom filter in bits, if [`HotTree::enable_bloom`] was called.
+    pub fn bloom_bits(&self) -> Option<usize> {
+        self.bloom.as_ref().map(BloomFilter::num_bits)
+    }
 
-            let (left, right) = if new_bit == 0 {
-                (new_leaf, self.root)
-            } else {
-                (self.root, new_leaf)
-            };
+    /// Expected false-positive rate of the Bloom filter for the keys added to it so far
+    /// (including removed ones), if [`HotTree::enable_bloom`] was called.
+    pub fn bloom_false_positive_rate(&self) -> Option<f64> {
+        self.bloom.as_ref().map(BloomFilter::false_positive_rate)
+    }
 
-            self.root = self.create_two_entries_node(diff_bit, left, right);
-            return None;
+    /// The backing value slots, for bulk updates without descending the trie.
+    ///
+    /// Slots are in insertion order, not key order, and slots freed by removal appear as
+    /// `None`. Only modify values in place: the tree relies on a slot being `Some` exactly
+    /// while its key is live, so filling or emptying a slot leaves lookups, `len` and
+    /// handles inconsistent. Empty for zero-sized values and with
+    /// [`HotTree::with_inline_values`], which use no slots.
+    ///
+    /// With change tracking, every live value counts as changed by this call.
+    pub fn values_mut(&mut self) -> &mut [Option<V>] {
+        if let Some(stamps) = &mut self.change_stamps {
+            for (stamp, value) in stamps.iter_mut().zip(&self.values) {
+                if value.is_some() {
+                    *stamp = self.change_generation;
+                }
+            }
+            self.change_generation += 1;
         }
+        &mut self.values
+    }
 
-        // Traverse compound nodes to a leaf, recording a stack of (node, entry_idx).
-        let mut stack: Vec<InsertFrame> = Vec::with_capacity(64);
-        let mut current = self.root;
-        while !current.is_leaf() {
-            let node_off = current.node_off();
-            let entry_idx = self.node_descend_index(node_off, key);
-            let child = self.node_entry_ptr(node_off, entry_idx);
-            let msb = self.node_most_significant_bit(node_off);
-            stack.push(InsertFrame {
-                node_off,
-                entry_idx,
-                msb,
-            });
-            current = child;
+    /// Record the generation of every value write, for [`HotTree::iter_changed_since`].
+    ///
+    /// Costs 8 bytes per value slot. Values already present count as written at
+    /// generation 0. Calling this again keeps the recorded generations.
+    ///
+    /// # Panics
+    ///
+    /// Panics for zero-sized values and with [`HotTree::with_inline_values`], which have
+    /// no value slots to stamp.
+    pub fn enable_change_tracking(&mut self) {
+        assert!(
+            std::mem::size_of::<V>() != 0 && !self.config.inline_values,
+            "change tracking needs value slots"
+        );
+        if self.change_stamps.is_none() {
+            self.change_stamps = Some(vec![0; self.values.len()]);
+            self.change_generation = self.change_generation.max(1);
         }
+    }
 
-        let leaf_off = current.leaf_off();
-        if self.leaf_key_equals(leaf_off, key) {
-            if std::mem::size_of::<V>() == 0 {
-                if current.is_tombstone() {
-                    let parent = stack.last().expect("root is node so stack is non-empty");
-                    self.node_set_entry_ptr(
-                        parent.node_off,
-                        parent.entry_idx,
-                        current.without_tombstone(),
-                    );
-                    self.zst_values.push(value);
-                    self.count += 1;
-                    return None;
-                }
-                let old = self
-                    .zst_values
-                    .pop()
-                    .expect("ZST values must track live key count");
-                self.zst_values.push(value);
-                return Some(old);
+    /// The generation the next value write will be stamped with. Pass a recorded
+    /// generation to [`HotTree::iter_changed_since`] to see what was written after it.
+    pub fn change_generation(&self) -> u64 {
+        self.change_generation
+    }
+
+    /// Entries, in key order, whose value was inserted, replaced or mutably borrowed at
+    /// or after generation `gen`.
+    ///
+    /// Walks every leaf. Removed keys are not reported; pair this with a record of removals
+    /// for full change capture.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless [`HotTree::enable_change_tracking`] was called.
+    pub fn iter_changed_since(&self, gen: u64) -> impl Iterator<Item = (Vec<u8>, &V)> + '_ {
+        let stamps = self
+            .change_stamps
+            .as_ref()
+            .expect("iter_changed_since needs enable_change_tracking");
+        let mut walk = LeafWalk::new(self);
+        std::iter::from_fn(move || loop {
+            let (leaf_off, value) = walk.next_leaf()?;
+            if stamps[self.get_leaf_value_idx(leaf_off)] >= gen {
+                return Some((self.get_leaf_key(leaf_off), value));
             }
+        })
+    }
 
-            let idx = self.get_leaf_value_idx(leaf_off);
-            let old = self.values[idx].replace(value);
-            if old.is_none() {
-                self.count += 1;
+    /// Descend to the leaf storing `key`, if any.
+    fn find_leaf(&self, key: &[u8]) -> Option<Ptr> {
+        if self.root.is_null() {
+            return None;
+        }
+        if let Some(bloom) = &self.bloom {
+            if !bloom.may_contain(self.hash_bytes(key)) {
+                #[cfg(test)]
+                BLOOM_SKIPS.with(|skips| skips.set(skips.get() + 1));
+                return None;
             }
-            return old;
         }
 
-        let diff_bit = self
-            .first_diff_bit_leaf(leaf_off, key)
-            .expect("non-equal keys must have a first differing bit");
+        let mut current = self.root;
 
-        let new_leaf = self.store_leaf(key);
-        if std::mem::size_of::<V>() == 0 {
-            self.zst_values.push(value);
-        } else {
-            self.values.push(Some(value));
+        loop {
+            if current.is_leaf() {
+                return self
+                    .leaf_key_equals(current.leaf_off(), key)
+                    .then_some(current);
+            }
+
+            current = self.node_descend(current.node_off(), key);
         }
-        self.count += 1;
+    }
 
-        let mut insert_depth = 0usize;
-        while insert_depth + 1 < stack.len() && diff_bit > stack[insert_depth + 1].msb {
-            insert_depth += 1;
+    /// Resolve `key` once so repeated lookups via [`HotTree::get_prepared`] skip the descent.
+    ///
+    /// The result is tied to this tree's current key set; after a key is inserted or
+    /// removed, `get_prepared` falls back to an ordinary lookup.
+    pub fn prepare(&self, key: &[u8]) -> PreparedKey {
+        PreparedKey {
+            key: key.to_vec(),
+            leaf: self.find_leaf(key),
+            tree_id: self.id,
+            generation: self.generation,
         }
+    }
 
-        self.insert_at_depth(&stack, insert_depth, key, diff_bit, new_leaf);
-        None
+    /// Look up a key resolved by [`HotTree::prepare`], falling back to [`HotTree::get`] if
+    /// it was prepared by another tree (including a clone) or before a key was added or
+    /// removed.
+    pub fn get_prepared(&self, prepared: &PreparedKey) -> Option<&V> {
+        if prepared.tree_id != self.id || prepared.generation != self.generation {
+            return self.get(&prepared.key);
+        }
+        self.leaf_value(prepared.leaf?)
     }
 
-    #[inline]
-    fn ptr_height(&self, ptr: Ptr) -> u8 {
-        if ptr.is_null() || ptr.is_leaf() {
+    /// Whether `key` is present, without reading its value slot.
+    ///
+    /// Removal unlinks a key's leaf in the same step that empties its slot, so every leaf
+    /// reachable from the root holds a live value and a key match alone decides presence.
+    /// Removed keys' records stay in the leaf arena with `None` slots, but are never reached.
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.find_leaf(key.as_ref()).is_some_and(|leaf| {
+            debug_assert!(
+                self.leaf_value(leaf).is_some(),
+                "reachable leaf without a value"
+            );
+            true
+        })
+    }
+
+    /// Insert `key`, returning the previous value.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`HotTree::try_insert`] would return an error.
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: V) -> Option<V> {
+        self.try_insert(key, value)
+            .unwrap_or_else(|e| panic!("HotTree::insert: {e}"))
+    }
+
+    /// Insert `key` only if it is absent. If it is present, its value is left untouched and
+    /// `value` is handed back as `Err(value)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`HotTree::try_insert`] would return an error.
+    pub fn insert_unique(&mut self, key: impl AsRef<[u8]>, value: V) -> Result<(), V> {
+        let key = key.as_ref();
+        if self.contains_key(key) {
+            return Err(value);
+        }
+        let old = self.insert(key, value);
+        debug_assert!(old.is_none());
+        Ok(())
+    }
+
+    /// Insert `key`, returning the previous value, or an error if the key violates the
+    /// tree's configured limits. On error the tree is unchanged and `value` is dropped.
+    pub fn try_insert(&mut self, key: impl AsRef<[u8]>, value: V) -> Result<Option<V>, HotError> {
+        self.try_insert_leaf(key.as_ref(), value, None)
+            .map(|(old, _)| old)
+    }
+
+    /// [`HotTree::try_insert`], also returning the leaf now holding `key`. A new key is
+    /// stored with `key[..prefix_len]` as its prefix if `prefix_hint` is `Some(prefix_len)`.
+    fn try_insert_leaf(
+        &mut self,
+        key: &[u8],
+        value: V,
+        prefix_hint: Option<usize>,
+    ) -> Result<(Option<V>, Ptr), HotError> {
+        if key.len() > self.config.max_key_len {
+            return Err(HotError::KeyTooLong {
+                len: key.len(),
+                max: self.config.max_key_len,
+            });
+        }
+        // These checks only matter for new keys; updates never grow the trie or use a slot.
+        let height = self.ptr_height(self.root);
+        let max_slots = self.config.value_index_width.max_slots();
+        let limit = self.config.arena_limit;
+        let error = if height >= self.config.max_height {
+            Some(HotError::HeightLimit {
+                max: self.config.max_height,
+            })
+        } else if std::mem::size_of::<V>() != 0 && self.values.len() >= max_slots {
+            Some(HotError::ValueIndexOverflow { max: max_slots })
+        } else if self.leaves.len() + key.len() + MAX_LEAF_OVERHEAD > limit
+            // An insert adds or replaces at most two nodes per level.
+            || self.nodes.data.len() + 2 * (height as usize + 1) * MAX_NODE_SIZE > limit
+        {
+            Some(HotError::ArenaFull { max: limit })
+        } else {
+            self.config
+                .memory_budget
+                .filter(|&budget| {
+                    self.memory_usage() + self.insert_growth_bound(key, height, prefix_hint)
+                        > budget
+                })
+                .map(|max| HotError::MemoryBudget { max })
+        };
+        if let Some(error) = error {
+            if !self.contains_key(key) {
+                return Err(error);
+            }
+        }
+        let (old, leaf) = self.insert_unchecked(key, value, prefix_hint)?;
+        if old.is_none() {
+            self.generation = self.generation.wrapping_add(1);
+            if self.bloom.is_some() {
+                let hash = self.hash_bytes(key);
+                if let Some(bloom) = &mut self.bloom {
+                    bloom.insert(hash);
+                }
+            }
+        }
+        Ok((old, leaf))
+    }
+
+    /// Upper bound on the bytes [`HotTree::memory_usage`] can grow by when inserting the new
+    /// key `key` into a trie of height `height`.
+    fn insert_growth_bound(&self, key: &[u8], height: u8, prefix_hint: Option<usize>) -> usize {
+        let growth = self.config.growth;
+        // Bytes `policy` adds to a buffer of `elem_size`-byte elements over `count` separate
+        // reserves of `step` elements.
+        let grown = |policy: GrowthPolicy, len, cap, step, count, elem_size| {
+            let mut new_cap = cap;
+            for i in 0..count {
+                new_cap = policy.grown_capacity(len + i * step, new_cap, step, elem_size);
+            }
+            (new_cap - cap) * elem_size
+        };
+        // An insert adds or replaces at most two nodes per level, freeing the ones replaced.
+        let node_writes = 2 * (height as usize + 1);
+
+        let words = &self.leaves.words;
+        let leaf_words = (key.len() + MAX_LEAF_OVERHEAD).div_ceil(4) + 1;
+        let mut bytes = grown(growth, words.len(), words.capacity(), leaf_words, 1, 4);
+        let data = &self.nodes.data;
+        bytes += grown(
+            growth,
+            data.len(),
+            data.capacity(),
+            MAX_NODE_SIZE,
+            node_writes,
+            1,
+        );
+
+        // At most `node_writes` lists take freed nodes, one push each; charge the largest
+        // growths a list can see taking all of them.
+        let mut free_growth: Vec<usize> = self
+            .nodes
+            .free
+            .iter()
+            .filter(|list| list.capacity() - list.len() < node_writes)
+            .map(|list| {
+                let (len, cap) = (list.len(), list.capacity());
+                grown(GrowthPolicy::Doubling, len, cap, 1, node_writes, 8)
+            })
+            .collect();
+        free_growth.sort_unstable_by(|a, b| b.cmp(a));
+        bytes += free_growth.iter().take(node_writes).sum::<usize>();
+
+        if std::mem::size_of::<V>() != 0 && !self.config.inline_values {
+            let slot = std::mem::size_of::<Option<V>>();
+            let values = &self.values;
+            bytes += grown(
+                GrowthPolicy::Doubling,
+                values.len(),
+                values.capacity(),
+                1,
+                1,
+                slot,
+            );
+            if let Some(stamps) = &self.change_stamps {
+                bytes += grown(
+                    GrowthPolicy::Doubling,
+                    stamps.len(),
+                    stamps.capacity(),
+                    1,
+                    1,
+                    8,
+                );
+            }
+        }
+
+        let prefix = prefix_hint.map_or_else(|| self.natural_prefix(key), |len| &key[..len]);
+        let learns_prefix = self.config.fixed_key_len.is_none()
+            && (self.config.intern_suffixes
+                || (!prefix.is_empty()
+                    && self.shared_prefix_id(prefix).is_none()
+                    && !self.prefix_hash.contains_key(&self.hash_bytes(prefix))));
+        if learns_prefix {
+            // A prefix and a tail, each no longer than the key and registered separately.
+            let pool = &self.prefix_pool;
+            let offsets = &self.prefix_offsets;
+            let refs = &self.prefix_refs;
+            bytes += grown(
+                GrowthPolicy::Doubling,
+                pool.len(),
+                pool.capacity(),
+                key.len(),
+                2,
+                1,
+            );
+            bytes += grown(
+                GrowthPolicy::Doubling,
+                offsets.len(),
+                offsets.capacity(),
+                1,
+                2,
+                4,
+            );
+            bytes += grown(GrowthPolicy::Doubling, refs.len(), refs.capacity(), 1, 2, 4);
+            if self.prefix_hash.capacity() - self.prefix_hash.len() < 2 {
+                bytes += (self.prefix_hash.capacity().max(3) + 1) * 16;
+            }
+            if self.config.intern_suffixes {
+                bytes += self.tail_sketch.record_growth();
+            }
+        }
+        bytes
+    }
+
+    /// Insert `key`, storing it (if new) with `key[..prefix_len]` as its shared prefix
+    /// instead of detecting a prefix at its first delimiter.
+    ///
+    /// For batches whose common prefix is known, this skips the delimiter scan and lets the
+    /// prefix end anywhere. A prefix the table cannot take (it is full, or the prefix's hash
+    /// collides) is not applied, as with detected prefixes. Updates keep the key's existing
+    /// leaf. A `prefix_len` of 0 stores the key without a prefix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len > key.len()`, or where [`HotTree::try_insert`] would return an
+    /// error.
+    pub fn insert_with_prefix_hint(
+        &mut self,
+        key: impl AsRef<[u8]>,
+        value: V,
+        prefix_len: usize,
+    ) -> Option<V> {
+        let key = key.as_ref();
+        assert!(
+            prefix_len <= key.len(),
+            "prefix hint {prefix_len} exceeds key length {}",
+            key.len()
+        );
+        self.try_insert_leaf(key, value, Some(prefix_len))
+            .unwrap_or_else(|e| panic!("HotTree::insert_with_prefix_hint: {e}"))
+            .0
+    }
+
+    /// Insert `key` and return a [`Handle`] for O(1) access to its value.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`HotTree::try_insert`] would return an error.
+    pub fn insert_with_handle(&mut self, key: impl AsRef<[u8]>, value: V) -> (Option<V>, Handle) {
+        let (old, leaf) = self
+            .try_insert_leaf(key.as_ref(), value, None)
+            .unwrap_or_else(|e| panic!("HotTree::insert_with_handle: {e}"));
+        (old, self.handle_for(leaf))
+    }
+
+    /// Insert `key`, dropping any previous value, and return the stored value for
+    /// finishing its initialization in place without a second descent.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`HotTree::try_insert`] would return an error.
+    pub fn insert_and_get_mut(&mut self, key: impl AsRef<[u8]>, value: V) -> &mut V {
+        let (_, leaf) = self
+            .try_insert_leaf(key.as_ref(), value, None)
+            .unwrap_or_else(|e| panic!("HotTree::insert_and_get_mut: {e}"));
+        self.leaf_value_mut(leaf)
+            .expect("a just-inserted key has a value")
+    }
+
+    fn handle_for(&self, leaf: Ptr) -> Handle {
+        let slot = if std::mem::size_of::<V>() == 0 || self.config.inline_values {
+            0
+        } else {
+            self.get_leaf_value_idx(leaf.leaf_off()) as u32
+        };
+        Handle {
+            leaf,
+            slot,
+            epoch: self.leaf_epoch,
+        }
+    }
+
+    /// Value behind `handle`, or `None` if its key has since been removed.
+    ///
+    /// O(1) for sized values. Zero-sized and inline values have no slot to check, so
+    /// liveness is confirmed by looking the key up again.
+    pub fn get_by_handle(&self, handle: Handle) -> Option<&V> {
+        if handle.epoch != self.leaf_epoch {
+            return None;
+        }
+        if std::mem::size_of::<V>() == 0 || self.config.inline_values {
+            return self.live_leaf_value(handle.leaf);
+        }
+        self.values.get(handle.slot as usize)?.as_ref()
+    }
+
+    /// Value of the leaf record `leaf`, or `None` if its key has since been removed.
+    ///
+    /// Without value slots to mark removal, this costs a lookup of the leaf's key.
+    fn live_leaf_value(&self, leaf: Ptr) -> Option<&V> {
+        if std::mem::size_of::<V>() == 0 || self.config.inline_values {
+            let key = self.get_leaf_key(leaf.leaf_off());
+            if self.find_leaf(&key) != Some(leaf) {
+                return None;
+            }
+        }
+        self.leaf_value(leaf)
+    }
+
+    /// Remove the entry behind `handle`, returning its value, or `None` if its key has
+    /// already been removed.
+    ///
+    /// Nodes do not point back to their parents, so this descends once by the key read
+    /// back from the handle's leaf, at the cost of [`HotTree::remove`]; it saves the caller
+    /// from keeping the key. The entry is removed only if the descent reaches the handle's
+    /// own leaf, not one stored for the key after it was re-inserted.
+    pub fn remove_by_handle(&mut self, handle: Handle) -> Option<V> {
+        if handle.epoch != self.leaf_epoch {
+            return None;
+        }
+        if std::mem::size_of::<V>() != 0 && !self.config.inline_values {
+            self.values.get(handle.slot as usize)?.as_ref()?;
+        }
+        let key = self.get_leaf_key(handle.leaf.leaf_off());
+        let old = self.remove_leaf(&key, Some(handle.leaf))?;
+        self.generation = self.generation.wrapping_add(1);
+        Some(old)
+    }
+
+    /// Mutable [`HotTree::get_by_handle`].
+    pub fn get_by_handle_mut(&mut self, handle: Handle) -> Option<&mut V> {
+        if handle.epoch != self.leaf_epoch {
+            return None;
+        }
+        if std::mem::size_of::<V>() == 0 {
+            self.get_by_handle(handle)?;
+            return self.zst_values.last_mut();
+        }
+        if self.config.inline_values {
+            self.get_by_handle(handle)?;
+            // SAFETY: see `push_value`.
+            return Some(unsafe { &mut *self.inline_value_mut_ptr(handle.leaf.leaf_off()) });
+        }
+        let slot = handle.slot as usize;
+        self.values.get(slot)?.as_ref()?;
+        self.stamp_slot(slot);
+        self.values[slot].as_mut()
+    }
+
+    fn insert_unchecked(
+        &mut self,
+        key: &[u8],
+        value: V,
+        prefix_hint: Option<usize>,
+    ) -> Result<(Option<V>, Ptr), HotError> {
+        if self.root.is_null() {
+            let leaf_ptr = self.store_leaf(key, prefix_hint);
+            self.push_value(leaf_ptr, value);
+            self.root = leaf_ptr;
+            self.count += 1;
+            return Ok((None, leaf_ptr));
+        }
+
+        if self.root.is_leaf() {
+            let leaf_off = self.root.leaf_off();
+            if self.leaf_key_equals(leaf_off, key) {
+                if std::mem::size_of::<V>() == 0 {
+                    if self.root.is_tombstone() {
+                        self.root = self.root.without_tombstone();
+                        self.zst_values.push(value);
+                        self.count += 1;
+                        return Ok((None, self.root));
+                    }
+                    let old = self
+                        .zst_values
+                        .pop()
+                        .expect("ZST values must track live key count");
+                    self.zst_values.push(value);
+                    return Ok((Some(old), self.root));
+                }
+
+                let old = self.replace_value(leaf_off, value);
+                if old.is_none() {
+                    self.count += 1;
+                }
+                return Ok((old, self.root));
+            }
+
+            let diff_bit = self
+                .first_diff_bit_leaf(leaf_off, key)
+                .expect("keys that are not equal differ in some bit");
+
+            let new_leaf = self.store_leaf(key, prefix_hint);
+            self.push_value(new_leaf, value);
+            self.count += 1;
+
+            let new_bit = Self::bit_at(key, diff_bit);
+            let existing_bit = self.bit_at_leaf(leaf_off, diff_bit);
+            debug_assert_ne!(new_bit, existing_bit);
+
+            let (left, right) = if new_bit == 0 {
+                (new_leaf, self.root)
+            } else {
+                (self.root, new_leaf)
+            };
+
+            self.root = self.create_two_entries_node(diff_bit, left, right);
+            return Ok((None, new_leaf));
+        }
+
+        // Traverse compound nodes to a leaf, recording a stack of (node, entry_idx).
+        let mut stack: Vec<InsertFrame> = Vec::with_capacity(64);
+        let mut current = self.root;
+        while !current.is_leaf() {
+            let node_off = current.node_off();
+            let entry_idx = self.node_descend_index(node_off, key);
+            let child = self.node_entry_ptr(node_off, entry_idx);
+            let msb = self.node_most_significant_bit(node_off);
+            stack.push(InsertFrame {
+                node_off,
+                entry_idx,
+                msb,
+            });
+            current = child;
+        }
+
+        let leaf_off = current.leaf_off();
+        if self.leaf_key_equals(leaf_off, key) {
+            if std::mem::size_of::<V>() == 0 {
+                if current.is_tombstone() {
+                    let parent = stack.last().expect("root is node so stack is non-empty");
+                    self.node_set_entry_ptr(
+                        parent.node_off,
+                        parent.entry_idx,
+                        current.without_tombstone(),
+                    );
+                    self.zst_values.push(value);
+                    self.count += 1;
+                    return Ok((None, current.without_tombstone()));
+                }
+                let old = self
+                    .zst_values
+                    .pop()
+                    .expect("ZST values must track live key count");
+                self.zst_values.push(value);
+                return Ok((Some(old), current));
+            }
+
+            let old = self.replace_value(leaf_off, value);
+            if old.is_none() {
+                self.count += 1;
+            }
+            return Ok((old, current));
+        }
+
+        let diff_bit = self
+            .first_diff_bit_leaf(leaf_off, key)
+            .expect("keys that are not equal differ in some bit");
+
+        let new_leaf = self.store_leaf(key, prefix_hint);
+        self.push_value(new_leaf, value);
+        self.count += 1;
+
+        let mut insert_depth = 0usize;
+        while insert_depth + 1 < stack.len() && diff_bit > stack[insert_depth + 1].msb {
+            insert_depth += 1;
+        }
+
+        self.insert_at_depth(&stack, insert_depth, key, diff_bit, new_leaf);
+        Ok((None, new_leaf))
+    }
+
+    #[inline]
+    fn ptr_height(&self, ptr: Ptr) -> u8 {
+        if ptr.is_null() || ptr.is_leaf() {
             0
         } else {
             self.nodes.height(ptr.node_off())
@@ -2072,15 +4210,17 @@ impl<V> HotTree<V> {
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn view_affected_range(
         mapping: DiscriminativeBitsRepresentation,
         sparse: &[u32; MAX_COMPOUND_ENTRIES],
         n: usize,
         entry_idx: usize,
         disc: u16,
+        simd: SimdSupport,
     ) -> (usize, usize, u32, u32) {
         debug_assert!(entry_idx < n);
-        let prefix_bits = mapping.prefix_mask_u32(disc);
+        let prefix_bits = mapping.prefix_mask_u32(disc, simd);
         let subtree_prefix = sparse[entry_idx] & prefix_bits;
 
         let mut first: Option<usize> = None;
@@ -2178,6 +4318,7 @@ impl<V> HotTree<V> {
         left | right
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn node_remove_entry(&mut self, node_off: u64, entry_idx: usize) -> Ptr {
         let tag = self.nodes.tag(node_off);
         match tag {
@@ -2213,7 +4354,14 @@ impl<V> HotTree<V> {
                 let disc_abs = disc_bits[(k - 1) - disc_dense_pos];
 
                 let (range_first, range_count, _prefix_bits, _subtree_prefix) =
-                    Self::view_affected_range(mapping, &sparse, n, entry_idx, disc_abs);
+                    Self::view_affected_range(
+                        mapping,
+                        &sparse,
+                        n,
+                        entry_idx,
+                        disc_abs,
+                        self.config.simd,
+                    );
                 debug_assert!(range_count >= 2);
                 let range_last = range_first + range_count;
                 if disc_value == 0 {
@@ -2243,17 +4391,21 @@ impl<V> HotTree<V> {
                 let mut new_sparse = [0u32; MAX_COMPOUND_ENTRIES];
                 let mut new_ptrs = [Ptr::NULL; MAX_COMPOUND_ENTRIES];
                 for i in 0..entry_idx {
-                    new_sparse[i] = pext_u64(sparse[i] as u64, compression_mask as u64) as u32;
+                    new_sparse[i] =
+                        pext_u64(sparse[i] as u64, compression_mask as u64, self.config.simd)
+                            as u32;
                     new_ptrs[i] = ptrs[i];
                 }
                 for i in (entry_idx + 1)..n {
                     let j = i - 1;
-                    new_sparse[j] = pext_u64(sparse[i] as u64, compression_mask as u64) as u32;
+                    new_sparse[j] =
+                        pext_u64(sparse[i] as u64, compression_mask as u64, self.config.simd)
+                            as u32;
                     new_ptrs[j] = ptrs[i];
                 }
 
                 let compressed_disc_mask =
-                    pext_u64(disc_mask as u64, compression_mask as u64) as u32;
+                    pext_u64(disc_mask as u64, compression_mask as u64, self.config.simd) as u32;
                 if compressed_disc_mask != 0 {
                     let delete_unused_mask = !compressed_disc_mask;
                     for orig_i in range_first..range_last {
@@ -2304,6 +4456,7 @@ impl<V> HotTree<V> {
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn build_subtree_from_range(
         &mut self,
         mapping: DiscriminativeBitsRepresentation,
@@ -2357,7 +4510,7 @@ impl<V> HotTree<V> {
 
         for i in 0..count {
             let pk = sparse[start + i];
-            new_sparse[i] = pext_u64(pk as u64, relevant as u64) as u32;
+            new_sparse[i] = pext_u64(pk as u64, relevant as u64, self.config.simd) as u32;
             new_ptrs[i] = ptrs[start + i];
         }
         new_sparse[0] = 0;
@@ -2379,6 +4532,7 @@ impl<V> HotTree<V> {
         Ptr::node(off)
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn node_add_entry_by_bit(
         &mut self,
         node_off: u64,
@@ -2399,7 +4553,7 @@ impl<V> HotTree<V> {
         }
 
         let (first, count, prefix_bits, subtree_prefix) =
-            Self::view_affected_range(mapping, &sparse, n, entry_idx, disc);
+            Self::view_affected_range(mapping, &sparse, n, entry_idx, disc, self.config.simd);
         debug_assert!(count >= 1);
         let insert_idx = first + (new_bit as usize) * count;
 
@@ -2674,7 +4828,7 @@ impl<V> HotTree<V> {
     fn parent_pull_up(&mut self, stack: &[InsertFrame], parent_depth: usize, split: BiNodeSplit) {
         let parent_off = stack[parent_depth].node_off;
         let parent_n = self.node_entry_count(parent_off);
-        debug_assert!(parent_n >= 2 && parent_n <= MAX_COMPOUND_ENTRIES);
+        debug_assert!((2..=MAX_COMPOUND_ENTRIES).contains(&parent_n));
         debug_assert!(split.height == self.nodes.height(parent_off));
 
         let entry_idx = stack[parent_depth].entry_idx;
@@ -2726,7 +4880,7 @@ impl<V> HotTree<V> {
         debug_assert_eq!(n, node_n);
 
         let (first_idx, count, _prefix_bits, _subtree_prefix) =
-            Self::view_affected_range(mapping, &sparse, n, frame.entry_idx, disc);
+            Self::view_affected_range(mapping, &sparse, n, frame.entry_idx, disc, self.config.simd);
         debug_assert!(first_idx < node_n);
 
         let new_bit = Self::bit_at(key, disc);
@@ -2822,7 +4976,133 @@ impl<V> HotTree<V> {
         }
     }
 
-    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<V> {
+        let old = self.remove_unchecked(key.as_ref())?;
+        self.generation = self.generation.wrapping_add(1);
+        Some(old)
+    }
+
+    /// Remove a batch of keys, returning how many were present.
+    ///
+    /// The batch descends the trie together: each node routes its share of the keys to its
+    /// entries once, and a node that loses several entries is rebuilt once without all of
+    /// them, instead of once per removed key. The result holds the same entries as calling
+    /// [`HotTree::remove`] on each key, though its node layout may differ.
+    pub fn remove_many(&mut self, keys: &[&[u8]]) -> usize {
+        let mut sorted = keys.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if self.root.is_null() || sorted.is_empty() {
+            return 0;
+        }
+        let mut removed = 0;
+        self.root = self.remove_batch(self.root, &sorted, &mut removed);
+        if removed > 0 {
+            self.generation = self.generation.wrapping_add(1);
+        }
+        removed
+    }
+
+    /// Remove every key of `keys` stored under `ptr`, counting them in `removed`, and
+    /// return what replaces `ptr`: itself, a node rebuilt without its emptied entries, the
+    /// single entry left, or `Ptr::NULL` if none is.
+    fn remove_batch(&mut self, ptr: Ptr, keys: &[&[u8]], removed: &mut usize) -> Ptr {
+        if ptr.is_leaf() {
+            let leaf_off = ptr.leaf_off();
+            if !ptr.is_tombstone()
+                && keys.iter().any(|key| self.leaf_key_equals(leaf_off, key))
+                && self.release_leaf(leaf_off).is_some()
+            {
+                *removed += 1;
+                return Ptr::NULL;
+            }
+            return ptr;
+        }
+
+        let node_off = ptr.node_off();
+        let mut routed: Vec<(usize, &[u8])> = keys
+            .iter()
+            .map(|&key| (self.node_descend_index(node_off, key), key))
+            .collect();
+        // Stable, so each entry's keys stay sorted.
+        routed.sort_by_key(|&(entry_idx, _)| entry_idx);
+
+        let mut sparse = [0u32; MAX_COMPOUND_ENTRIES];
+        let mut ptrs = [Ptr::NULL; MAX_COMPOUND_ENTRIES];
+        let (n, _height, mapping) = self.export_node_view(node_off, &mut sparse, &mut ptrs);
+        let mut changed = false;
+        let mut group_keys = Vec::new();
+        for group in routed.chunk_by(|a, b| a.0 == b.0) {
+            let entry_idx = group[0].0;
+            group_keys.clear();
+            group_keys.extend(group.iter().map(|&(_, key)| key));
+            let child = self.remove_batch(ptrs[entry_idx], &group_keys, removed);
+            changed |= child != ptrs[entry_idx];
+            ptrs[entry_idx] = child;
+        }
+        if !changed {
+            return ptr;
+        }
+
+        // Re-derive the survivors' partial keys: each shares its predecessor's path down to
+        // their first differing bit, where it branches right, then keeps left.
+        let mut kept = 0;
+        let mut kept_sparse = [0u32; MAX_COMPOUND_ENTRIES];
+        let mut kept_ptrs = [Ptr::NULL; MAX_COMPOUND_ENTRIES];
+        let mut prev = 0;
+        for i in (0..n).filter(|&i| !ptrs[i].is_null()) {
+            if kept > 0 {
+                let branch = 1u32 << (31 - (sparse[prev] ^ sparse[i]).leading_zeros());
+                kept_sparse[kept] = (kept_sparse[kept - 1] & !(branch | (branch - 1))) | branch;
+            }
+            kept_ptrs[kept] = ptrs[i];
+            prev = i;
+            kept += 1;
+        }
+
+        if kept == n {
+            let mut height = 0u8;
+            for (i, &child) in ptrs[..n].iter().enumerate() {
+                self.node_set_entry_ptr(node_off, i, child);
+                height = height.max(self.ptr_height(child));
+            }
+            self.nodes.set_height(node_off, height + 1);
+            return ptr;
+        }
+        self.nodes.free_node(node_off);
+        if kept == 0 {
+            return Ptr::NULL;
+        }
+        self.build_subtree_from_range(mapping, &kept_sparse, &kept_ptrs, 0, kept)
+    }
+
+    /// Remove every entry whose key is strictly less than `bound`, yielding them in key
+    /// order.
+    ///
+    /// Meant for forward-sorted ingest that periodically flushes its low end: removal
+    /// collapses nodes exactly as [`HotTree::remove`] does, but leaf records stay in the
+    /// append-only leaf arena (see [`HotTree::dead_bytes`]). The entries are removed
+    /// eagerly, before the first one is yielded.
+    pub fn drain_below(&mut self, bound: &[u8]) -> impl Iterator<Item = (Vec<u8>, V)> {
+        let keys: Vec<Vec<u8>> = self
+            .iter()
+            .map(|(k, _)| k)
+            .take_while(|k| k.as_slice() < bound)
+            .collect();
+        let mut drained = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = self.remove(&key).expect("iterated key must be present");
+            drained.push((key, value));
+        }
+        drained.into_iter()
+    }
+
+    fn remove_unchecked(&mut self, key: &[u8]) -> Option<V> {
+        self.remove_leaf(key, None)
+    }
+
+    /// Remove `key`, provided its leaf is `expected` when given.
+    fn remove_leaf(&mut self, key: &[u8], expected: Option<Ptr>) -> Option<V> {
         if self.root.is_null() {
             return None;
         }
@@ -2842,22 +5122,10 @@ impl<V> HotTree<V> {
         }
 
         let leaf_off = current.leaf_off();
-        if !self.leaf_key_equals(leaf_off, key) {
+        if expected.is_some_and(|leaf| leaf != current) || !self.leaf_key_equals(leaf_off, key) {
             return None;
         }
-
-        let old = if std::mem::size_of::<V>() == 0 {
-            Some(
-                self.zst_values
-                    .pop()
-                    .expect("ZST values must track live key count"),
-            )
-        } else {
-            let idx = self.get_leaf_value_idx(leaf_off);
-            self.values[idx].take()
-        }?;
-
-        self.count -= 1;
+        let old = self.release_leaf(leaf_off)?;
 
         // Removing the root leaf.
         if stack.is_empty() {
@@ -2885,7 +5153,8 @@ impl<V> HotTree<V> {
             for i in 0..n {
                 h = h.max(self.ptr_height(self.node_entry_ptr(node_off, i)));
             }
-            h = h.saturating_add(1);
+            // Children sit strictly below the node's old height, so this cannot overflow.
+            h += 1;
             self.nodes.set_height(node_off, h);
             if h == old_h {
                 break;
@@ -2896,200 +5165,2732 @@ impl<V> HotTree<V> {
         Some(old)
     }
 
-    pub fn iter(&self) -> Iter<'_, V> {
-        let mut stack = Vec::new();
-        if !self.root.is_null() {
-            stack.push(self.root);
-        }
-        Iter { tree: self, stack }
-    }
-}
-
-impl<V> Default for HotTree<V> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl<V: Clone> Clone for HotTree<V> {
-    fn clone(&self) -> Self {
-        Self {
+    /// Take the value of a leaf that is about to be unlinked, and account its record as
+    /// dead.
+    fn release_leaf(&mut self, leaf_off: u64) -> Option<V> {
+        let old = if std::mem::size_of::<V>() == 0 {
+            Some(
+                self.zst_values
+                    .pop()
+                    .expect("ZST values must track live key count"),
+            )
+        } else {
+            self.take_value(leaf_off)
+        }?;
+
+        self.count -= 1;
+        self.dead_leaf_bytes += self.leaf_record_len(leaf_off);
+        let rec = self.leaf_record(leaf_off);
+        for id in [rec.prefix_id, rec.tail_id] {
+            if let Some(refs) = self.prefix_refs_mut(id) {
+                *refs -= 1;
+            }
+        }
+        Some(old)
+    }
+
+    /// Whether any key starts with `prefix`. Stops at the first candidate leaf.
+    pub fn contains_prefix(&self, prefix: &[u8]) -> bool {
+        let mut walk = LeafWalk::seek(self, prefix);
+        walk.next_leaf()
+            .is_some_and(|(leaf_off, _)| self.leaf_key(leaf_off).starts_with(prefix))
+    }
+
+    /// The smallest key starting with `prefix`, with its value. Stops at the first
+    /// candidate leaf, like [`HotTree::contains_prefix`].
+    pub fn first_with_prefix(&self, prefix: &[u8]) -> Option<(Vec<u8>, &V)> {
+        let (leaf_off, value) = LeafWalk::seek(self, prefix).next_leaf()?;
+        let key = self.leaf_key(leaf_off);
+        key.starts_with(prefix).then(|| {
+            let mut out = Vec::with_capacity(key.len());
+            key.extend_into(&mut out);
+            (out, value)
+        })
+    }
+
+    /// Approximate number of keys starting with `prefix`, from `max_samples` random
+    /// descents instead of a walk over every match.
+    ///
+    /// Each descent picks a uniformly random entry per node and weighs the leaf it reaches
+    /// by the product of the fan-outs on its path (Knuth's tree-size estimator), so the
+    /// estimate is unbiased, and its error shrinks as `max_samples` grows; a few hundred
+    /// samples typically land within 10–20% on large subtrees. It costs O(`max_samples` ×
+    /// height) against O(matches) for counting [`HotTree::prefix_scan`]. Samples are drawn
+    /// from a generator seeded by `prefix`, so repeated calls on an unchanged tree agree.
+    pub fn estimate_prefix_count(&self, prefix: &[u8], max_samples: usize) -> usize {
+        let subtrees = self.prefix_subtrees(prefix);
+        let nodes = subtrees.iter().filter(|ptr| !ptr.is_leaf()).count();
+        let per_subtree = (max_samples / nodes.max(1)).max(1);
+        let mut hasher = FnvHasher::default();
+        hasher.write(prefix);
+        let mut state = hasher.finish() | 1;
+        let mut next_random = move || {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut total = 0.0;
+        for root in subtrees {
+            if root.is_leaf() {
+                total += 1.0;
+                continue;
+            }
+            let mut sum = 0.0;
+            for _ in 0..per_subtree {
+                let (mut ptr, mut weight) = (root, 1.0);
+                while !ptr.is_leaf() {
+                    let n = self.node_entry_count(ptr.node_off());
+                    weight *= n as f64;
+                    ptr = self.node_entry_ptr(ptr.node_off(), (next_random() % n as u64) as usize);
+                }
+                if self.leaf_key(ptr.leaf_off()).starts_with(prefix) {
+                    sum += weight;
+                }
+            }
+            total += sum / per_subtree as f64;
+        }
+        total.round() as usize
+    }
+
+    /// Disjoint subtrees, in key order, that together hold every key starting with
+    /// `prefix`. Each starts with a matching key; only the last may extend past them.
+    fn prefix_subtrees(&self, prefix: &[u8]) -> Vec<Ptr> {
+        let mut stack = self.seek_stack(prefix);
+        stack.reverse();
+        // Matching keys are contiguous from the seek position, so once a subtree's
+        // smallest key does not match, neither does anything after it.
+        let end = stack
+            .iter()
+            .position(|&ptr| !self.leaf_key(self.leftmost_leaf(ptr)).starts_with(prefix))
+            .unwrap_or(stack.len());
+        stack.truncate(end);
+        stack
+    }
+
+    fn leftmost_leaf(&self, mut ptr: Ptr) -> u64 {
+        while !ptr.is_leaf() {
+            ptr = self.node_entry_ptr(ptr.node_off(), 0);
+        }
+        ptr.leaf_off()
+    }
+
+    /// Iterate in key order over the entries whose keys start with `prefix`.
+    ///
+    /// Seeks to the first such key, then walks leaves until a key no longer matches.
+    pub fn prefix_scan(&self, prefix: &[u8]) -> impl Iterator<Item = (Vec<u8>, &V)> + '_ {
+        let mut walk = LeafWalk::seek(self, prefix);
+        let prefix = prefix.to_vec();
+        std::iter::from_fn(move || {
+            let (leaf_off, value) = walk.next_leaf()?;
+            let key = self.leaf_key(leaf_off);
+            key.starts_with(&prefix).then(|| {
+                let mut out = Vec::with_capacity(key.len());
+                key.extend_into(&mut out);
+                (out, value)
+            })
+        })
+    }
+
+    /// Leaf-walk stack (see [`LeafWalk`]) whose next leaf is the first key `>= target`.
+    ///
+    /// Descends as a lookup would and finds where `target` diverges from the reached leaf.
+    /// In the node holding that divergence bit, the entries sharing the leaf's bits above it
+    /// form one subtree lying entirely on one side of `target`; every entry to the right of
+    /// the descent path is greater than `target`.
+    fn seek_stack(&self, target: &[u8]) -> Vec<Ptr> {
+        let mut stack = Vec::new();
+        if self.root.is_null() {
+            return stack;
+        }
+
+        let mut path: Vec<InsertFrame> = Vec::with_capacity(64);
+        let mut current = self.root;
+        while !current.is_leaf() {
+            let node_off = current.node_off();
+            let entry_idx = self.node_descend_index(node_off, target);
+            path.push(InsertFrame {
+                node_off,
+                entry_idx,
+                msb: self.node_most_significant_bit(node_off),
+            });
+            current = self.node_entry_ptr(node_off, entry_idx);
+        }
+        let leaf = current;
+
+        let Some(diff_bit) = self.first_diff_bit_leaf(leaf.leaf_off(), target) else {
+            // The target is stored: it is its own bound.
+            for frame in &path {
+                self.push_entries_rev(&mut stack, frame.node_off, frame.entry_idx + 1, usize::MAX);
+            }
+            stack.push(leaf);
+            return stack;
+        };
+        let target_below = Self::bit_at(target, diff_bit) == 0;
+
+        if path.is_empty() {
+            if target_below {
+                stack.push(leaf);
+            }
+            return stack;
+        }
+
+        let mut depth = 0usize;
+        while depth + 1 < path.len() && diff_bit > path[depth + 1].msb {
+            depth += 1;
+        }
+        for frame in &path[..depth] {
+            self.push_entries_rev(&mut stack, frame.node_off, frame.entry_idx + 1, usize::MAX);
+        }
+
+        let frame = &path[depth];
+        let mut sparse = [0u32; MAX_COMPOUND_ENTRIES];
+        let mut ptrs = [Ptr::NULL; MAX_COMPOUND_ENTRIES];
+        let (n, _h, mapping) = self.export_node_view(frame.node_off, &mut sparse, &mut ptrs);
+        let (first, count, _, _) = Self::view_affected_range(
+            mapping,
+            &sparse,
+            n,
+            frame.entry_idx,
+            diff_bit,
+            self.config.simd,
+        );
+        let start = if target_below { first } else { first + count };
+        self.push_entries_rev(&mut stack, frame.node_off, start, n);
+        stack
+    }
+
+    /// Push entries `from..to` (clamped to the entry count) of a node so that `from` is on top.
+    fn push_entries_rev(&self, stack: &mut Vec<Ptr>, node_off: u64, from: usize, to: usize) {
+        let to = to.min(self.node_entry_count(node_off));
+        for i in (from..to).rev() {
+            stack.push(self.node_entry_ptr(node_off, i));
+        }
+    }
+
+    /// Iterate in the order keys were first inserted, by scanning the append-only leaf
+    /// arena instead of descending the trie.
+    ///
+    /// Updating a key keeps its position; removing and re-inserting it moves it to the end.
+    /// Zero-sized and inline values pay a lookup per leaf record to skip removed keys.
+    pub fn iter_insertion_order(&self) -> impl Iterator<Item = (Vec<u8>, &V)> + '_ {
+        let mut off = 0;
+        std::iter::from_fn(move || {
+            while off < self.leaves.len() {
+                let leaf = Ptr::leaf(off as u64);
+                off += self.leaf_record_len(leaf.leaf_off());
+                if let Some(value) = self.live_leaf_value(leaf) {
+                    return Some((self.get_leaf_key(leaf.leaf_off()), value));
+                }
+            }
+            None
+        })
+    }
+
+    /// Decode every record in the leaf arena, in insertion order, including records of
+    /// removed keys. For bug reports; see [`LeafDebug`].
+    pub fn debug_entries(&self) -> Vec<LeafDebug> {
+        let mut entries = Vec::new();
+        let mut off = 0;
+        while off < self.leaves.len() {
+            let leaf = Ptr::leaf(off as u64);
+            let rec = self.leaf_record(leaf.leaf_off());
+            let has_slot = std::mem::size_of::<V>() != 0 && !self.config.inline_values;
+            entries.push(LeafDebug {
+                key: self.get_leaf_key(leaf.leaf_off()),
+                ptr_bits: leaf.0,
+                prefix_id: rec.prefix_id,
+                tail_id: rec.tail_id,
+                suffix_len: rec.suffix_len,
+                value_idx: has_slot.then(|| self.get_leaf_value_idx(leaf.leaf_off())),
+                is_tombstone: self.live_leaf_value(leaf).is_none(),
+            });
+            off += self.leaf_record_len(leaf.leaf_off());
+        }
+        entries
+    }
+
+    /// Iterate in ascending key order (see [`SortedIterator`]).
+    pub fn iter(&self) -> Iter<'_, V, S> {
+        Iter {
+            walk: LeafWalk::new(self),
+        }
+    }
+
+    /// Iterate in ascending key order over the keys in `range`.
+    pub fn range<'k>(
+        &self,
+        range: impl RangeBounds<&'k [u8]>,
+    ) -> impl Iterator<Item = (Vec<u8>, &V)> + '_ {
+        let (iter, skip) = match range.start_bound() {
+            Bound::Included(k) => (self.iter_from(k), None),
+            Bound::Excluded(k) => (self.iter_from(k), Some(k.to_vec())),
+            Bound::Unbounded => (self.iter(), None),
+        };
+        let end = range.end_bound().map(|k| k.to_vec());
+        iter.skip_while(move |(k, _)| skip.as_ref() == Some(k))
+            .take_while(move |(k, _)| match &end {
+                Bound::Included(end) => k <= end,
+                Bound::Excluded(end) => k < end,
+                Bound::Unbounded => true,
+            })
+    }
+
+    /// Wrap the tree for shared read-only access, releasing spare arena capacity first.
+    pub fn into_frozen(mut self) -> FrozenHotTree<V, S> {
+        self.shrink_to_fit();
+        FrozenHotTree::new(self)
+    }
+
+    /// [`HotTree::iter`], named for call sites that rely on ascending key order.
+    pub fn iter_sorted(&self) -> Iter<'_, V, S> {
+        self.iter()
+    }
+
+    /// Iterate in ascending key order starting at the first key `>= key`.
+    ///
+    /// `key` need not be present, so a resumable scan can persist the last key it emitted
+    /// and continue with `iter_from(&last).skip_while(|(k, _)| *k == last)`, even if that
+    /// key has since been removed.
+    pub fn iter_from(&self, key: &[u8]) -> Iter<'_, V, S> {
+        Iter {
+            walk: LeafWalk::seek(self, key),
+        }
+    }
+
+    /// Iterate in key order, yielding each key split into its shared prefix and
+    /// remaining suffix. `prefix ++ suffix` is the full key. The suffix is borrowed from
+    /// the leaf, so no allocation is performed per entry, unless suffix interning is
+    /// enabled and the key has an interned tail.
+    pub fn iter_parts(&self) -> IterParts<'_, V, S> {
+        IterParts {
+            walk: LeafWalk::new(self),
+        }
+    }
+
+    /// Entries grouped by the shared prefix they are stored under, one group per prefix in
+    /// ascending prefix order, each holding `(suffix, value)` pairs in key order.
+    ///
+    /// Keys stored without a prefix form the group of the empty prefix. Suffixes are as in
+    /// [`HotTree::iter_parts`]. Each key is in exactly one group, but the key ranges of
+    /// groups can interleave: unprefixed keys fall anywhere, and a hinted prefix may extend
+    /// another. All groups are built before the first is returned.
+    pub fn prefix_groups(&self) -> impl Iterator<Item = PrefixGroup<'_, V>> {
+        let mut groups: HashMap<u16, PrefixGroup<'_, V>> = HashMap::new();
+        let mut walk = LeafWalk::new(self);
+        while let Some((leaf_off, value)) = walk.next_leaf() {
+            let prefix_id = self.leaf_record(leaf_off).prefix_id;
+            let leaf = self.leaf_key(leaf_off);
+            let suffix = if leaf.tail.is_empty() {
+                Cow::Borrowed(leaf.mid)
+            } else {
+                Cow::Owned([leaf.mid, leaf.tail].concat())
+            };
+            let (_, entries) = groups
+                .entry(prefix_id)
+                .or_insert_with(|| (PrefixRef(self.get_prefix(prefix_id)), Vec::new()));
+            entries.push((suffix, value));
+        }
+        let mut groups: Vec<_> = groups.into_values().collect();
+        groups.sort_unstable_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+        groups.into_iter()
+    }
+
+    /// Every key concatenated in ascending order, with an offset table locating each one,
+    /// for handing the key set to another process as two flat buffers.
+    ///
+    /// `offsets` has `len() + 1` entries, starting at 0 and ending with the total blob
+    /// length, so key `i` is `blob[offsets[i] as usize..offsets[i + 1] as usize]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys total more than `u32::MAX` bytes.
+    pub fn keys_blob(&self) -> (Vec<u8>, Vec<u32>) {
+        let mut blob = Vec::new();
+        let mut offsets = Vec::with_capacity(self.count + 1);
+        offsets.push(0);
+        let mut walk = LeafWalk::new(self);
+        while let Some((leaf_off, _)) = walk.next_leaf() {
+            self.leaf_key(leaf_off).extend_into(&mut blob);
+            offsets.push(u32::try_from(blob.len()).expect("HotTree key bytes exceed u32::MAX"));
+        }
+        (blob, offsets)
+    }
+
+    /// Iterate in key order, borrowing each key from the leaf arena when it was stored
+    /// without a shared prefix (or interned tail), and allocating only when the pieces
+    /// must be joined.
+    pub fn iter_cow(&self) -> IterCow<'_, V, S> {
+        IterCow {
+            walk: LeafWalk::new(self),
+        }
+    }
+
+    /// Iterate in key order, yielding each entry with the number of nodes descended to
+    /// reach its leaf, at most [`HotTree::height`].
+    pub fn iter_by_depth(&self) -> impl Iterator<Item = (Vec<u8>, &V, u8)> + '_ {
+        let mut stack = Vec::new();
+        if !self.root.is_null() {
+            stack.push((self.root, 0u8));
+        }
+        std::iter::from_fn(move || {
+            while let Some((ptr, depth)) = stack.pop() {
+                if ptr.is_leaf() {
+                    if let Some(value) = self.leaf_value(ptr) {
+                        return Some((self.get_leaf_key(ptr.leaf_off()), value, depth));
+                    }
+                    continue;
+                }
+                let node_off = ptr.node_off();
+                for i in (0..self.node_entry_count(node_off)).rev() {
+                    stack.push((self.node_entry_ptr(node_off, i), depth + 1));
+                }
+            }
+            None
+        })
+    }
+
+    /// Iterate over every reachable leaf in key order, yielding `None` for leaves with no
+    /// value instead of skipping them as [`HotTree::iter`] does.
+    ///
+    /// Removal unlinks a key's leaf, so a valid tree has no such "ghost" leaves; they
+    /// appear only if a slot was emptied through [`HotTree::values_mut`]. Use this to audit
+    /// a tree for them.
+    pub fn iter_with_ghosts(&self) -> impl Iterator<Item = (Vec<u8>, Option<&V>)> + '_ {
+        let mut stack = Vec::new();
+        if !self.root.is_null() {
+            stack.push(self.root);
+        }
+        std::iter::from_fn(move || {
+            while let Some(ptr) = stack.pop() {
+                if ptr.is_leaf() {
+                    let key = self.get_leaf_key(ptr.leaf_off());
+                    return Some((key, self.leaf_value(ptr)));
+                }
+                let node_off = ptr.node_off();
+                for i in (0..self.node_entry_count(node_off)).rev() {
+                    stack.push(self.node_entry_ptr(node_off, i));
+                }
+            }
+            None
+        })
+    }
+
+    /// Call `f` with each entry in key order, reconstructing every key into `buf`.
+    ///
+    /// The key slice passed to `f` is only valid for that call; it is overwritten by the
+    /// next key. Once `buf` has grown to the longest key, iteration allocates nothing.
+    pub fn iter_into(&self, buf: &mut Vec<u8>, mut f: impl FnMut(&[u8], &V)) {
+        let mut walk = LeafWalk::new(self);
+        while let Some((leaf_off, value)) = walk.next_leaf() {
+            self.get_leaf_key_into(leaf_off, buf);
+            f(buf, value);
+        }
+    }
+}
+
+impl<V, S: BuildHasher + Default> Default for HotTree<V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<V, S: BuildHasher + Default> HotTree<V, S> {
+    /// Build a tree from `entries`, which must be in strictly ascending key order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not strictly ascending.
+    pub fn build_sorted<K: AsRef<[u8]>>(
+        entries: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<Self, HotError> {
+        let mut tree = Self::default();
+        let mut prev: Option<K> = None;
+        for (key, value) in entries {
+            if let Some(prev) = &prev {
+                assert!(
+                    prev.as_ref() < key.as_ref(),
+                    "build_sorted: keys must be strictly ascending"
+                );
+            }
+            tree.try_insert(key.as_ref(), value)?;
+            prev = Some(key);
+        }
+        Ok(tree)
+    }
+
+    /// Build a tree holding a copy of `map`, streaming its already-sorted entries into
+    /// [`HotTree::build_sorted`]. This is the simplest way to migrate from a `BTreeMap`.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`HotTree::try_insert`] would return an error.
+    pub fn from_btreemap(map: &BTreeMap<Vec<u8>, V>) -> Self
+    where
+        V: Clone,
+    {
+        Self::build_sorted(map.iter().map(|(key, value)| (key, value.clone())))
+            .unwrap_or_else(|e| panic!("HotTree::from_btreemap: {e}"))
+    }
+}
+
+impl<V: Clone, S: Clone> Clone for HotTree<V, S> {
+    fn clone(&self) -> Self {
+        Self {
             prefix_pool: self.prefix_pool.clone(),
             prefix_offsets: self.prefix_offsets.clone(),
+            prefix_refs: self.prefix_refs.clone(),
             prefix_hash: self.prefix_hash.clone(),
+            hash_builder: self.hash_builder.clone(),
+            shared_prefixes: self.shared_prefixes.clone(),
             leaves: self.leaves.clone(),
             values: self.values.clone(),
             zst_values: self.zst_values.clone(),
             nodes: self.nodes.clone(),
             root: self.root,
             count: self.count,
+            config: self.config,
+            id: next_tree_id(),
+            generation: self.generation,
+            dead_leaf_bytes: self.dead_leaf_bytes,
+            leaf_epoch: self.leaf_epoch,
+            bloom: self.bloom.clone(),
+            change_stamps: self.change_stamps.clone(),
+            change_generation: self.change_generation,
+            tail_sketch: self.tail_sketch.clone(),
             _marker: PhantomData,
         }
     }
-}
+}
+
+impl<V: std::fmt::Debug + Clone, S: BuildHasher> std::fmt::Debug for HotTree<V, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// `tree[key]` reads the value for `key`, accepting anything [`HotTree::get`] does by
+/// reference (`b"key"`, `"key"`, `&[u8]`).
+///
+/// # Panics
+///
+/// Panics if `key` is absent, as `HashMap` indexing does; use [`HotTree::get`] to test first.
+impl<K: AsRef<[u8]> + ?Sized, V, S: BuildHasher> std::ops::Index<&K> for HotTree<V, S> {
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key.as_ref()).expect("HotTree: key not found")
+    }
+}
+
+/// `tree[key] = value` overwrites the value for an existing `key`; it cannot insert.
+///
+/// # Panics
+///
+/// Panics if `key` is absent; use [`HotTree::insert`] to add keys.
+impl<K: AsRef<[u8]> + ?Sized, V, S: BuildHasher> std::ops::IndexMut<&K> for HotTree<V, S> {
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key.as_ref()).expect("HotTree: key not found")
+    }
+}
+
+/// Marker for iterators that yield entries in strictly ascending key order.
+///
+/// Keys compare as byte strings, as in `BTreeMap<Vec<u8>, V>`, and each key appears once.
+pub trait SortedIterator: Iterator {}
+
+impl<V, S: BuildHasher> SortedIterator for Iter<'_, V, S> {}
+impl<V, S: BuildHasher> SortedIterator for IterParts<'_, V, S> {}
+impl<V, S: BuildHasher> SortedIterator for IterCow<'_, V, S> {}
+
+/// Iterator over `(key, value)` borrowing keys stored without a shared prefix.
+pub struct IterCow<'a, V, S = FnvBuildHasher> {
+    walk: LeafWalk<'a, V, S>,
+}
+
+impl<'a, V, S: BuildHasher> Iterator for IterCow<'a, V, S> {
+    type Item = (Cow<'a, [u8]>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (leaf_off, value) = self.walk.next_leaf()?;
+        let leaf = self.walk.tree.leaf_key(leaf_off);
+        let key = if leaf.prefix.is_empty() && leaf.tail.is_empty() {
+            Cow::Borrowed(leaf.mid)
+        } else {
+            let mut key = Vec::new();
+            leaf.extend_into(&mut key);
+            Cow::Owned(key)
+        };
+        Some((key, value))
+    }
+}
+
+/// One leaf record, as reported by [`HotTree::debug_entries`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeafDebug {
+    pub key: Vec<u8>,
+    /// Raw bits of the 40-bit leaf pointer.
+    pub ptr_bits: u64,
+    /// Shared prefix id (0 = none).
+    pub prefix_id: u16,
+    /// Interned tail id (0 = none, or suffix interning disabled).
+    pub tail_id: u16,
+    /// Key bytes stored inline in the record.
+    pub suffix_len: usize,
+    /// Value slot, for values that have one.
+    pub value_idx: Option<usize>,
+    /// The record belongs to a removed key; its bytes remain until
+    /// [`HotTree::compact_leaves`].
+    pub is_tombstone: bool,
+}
+
+/// One trie node, as passed to the visitor of [`HotTree::walk_nodes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeView<'a> {
+    /// Node layout, as named by [`HotTree::node_tag_histogram`].
+    pub tag: &'static str,
+    /// Nodes on the longest path from this one down to a leaf, counting itself.
+    pub height: u8,
+    /// Key bit positions (MSB-first bit offsets) the node's entries differ at, ascending.
+    pub discriminative_bits: &'a [u16],
+    /// Kind of each entry's child, in key order.
+    pub children: &'a [ChildKind],
+}
+
+impl NodeView<'_> {
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+/// What a node entry points to; see [`NodeView::children`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildKind {
+    Leaf,
+    Node,
+}
+
+/// Stable reference to an entry, returned by [`HotTree::insert_with_handle`].
+///
+/// Value slots are never reused, so a handle stays valid until its own key is removed or
+/// [`HotTree::compact_leaves`] runs, after which lookups through it return `None`
+/// (re-inserting the key yields a new handle). Only meaningful for the tree that issued it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    leaf: Ptr,
+    slot: u32,
+    epoch: u32,
+}
+
+/// A key resolved against a [`HotTree`] by [`HotTree::prepare`].
+///
+/// Holds the key's leaf, with the tree's identity and generation at preparation time so
+/// that any other tree, or the same tree after its key set changes, looks the key up afresh.
+#[derive(Clone, Debug)]
+pub struct PreparedKey {
+    key: Vec<u8>,
+    leaf: Option<Ptr>,
+    tree_id: u64,
+    generation: u64,
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Descents skipped by [`HotTree::find_leaf`] because the Bloom filter ruled the key out.
+    static BLOOM_SKIPS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Identity for a newly created or cloned tree.
+fn next_tree_id() -> u64 {
+    static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+impl PreparedKey {
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+/// Depth-first walk over live leaves in key order, shared by the public iterators.
+struct LeafWalk<'a, V, S> {
+    tree: &'a HotTree<V, S>,
+    stack: Vec<Ptr>,
+}
+
+impl<'a, V, S: BuildHasher> LeafWalk<'a, V, S> {
+    fn new(tree: &'a HotTree<V, S>) -> Self {
+        let mut stack = Vec::new();
+        if !tree.root.is_null() {
+            stack.push(tree.root);
+        }
+        Self { tree, stack }
+    }
+
+    /// Walk starting at the first key `>= target`.
+    fn seek(tree: &'a HotTree<V, S>, target: &[u8]) -> Self {
+        Self {
+            tree,
+            stack: tree.seek_stack(target),
+        }
+    }
+
+    /// Next live leaf as `(leaf_off, value)`.
+    fn next_leaf(&mut self) -> Option<(u64, &'a V)> {
+        while let Some(ptr) = self.stack.pop() {
+            if ptr.is_null() {
+                continue;
+            }
+
+            if ptr.is_leaf() {
+                if let Some(value) = self.tree.leaf_value(ptr) {
+                    return Some((ptr.leaf_off(), value));
+                }
+                continue;
+            }
+
+            let node_off = ptr.node_off();
+            let n = self.tree.node_entry_count(node_off);
+            for i in (0..n).rev() {
+                self.stack.push(self.tree.node_entry_ptr(node_off, i));
+            }
+        }
+        None
+    }
+}
+
+pub struct Iter<'a, V, S = FnvBuildHasher> {
+    walk: LeafWalk<'a, V, S>,
+}
+
+impl<'a, V, S: BuildHasher> Iterator for Iter<'a, V, S> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (leaf_off, value) = self.walk.next_leaf()?;
+        Some((self.walk.tree.get_leaf_key(leaf_off), value))
+    }
+}
+
+/// Borrowed view of a shared key prefix stored in the tree's prefix pool.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PrefixRef<'a>(&'a [u8]);
+
+impl<'a> PrefixRef<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl std::ops::Deref for PrefixRef<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for PrefixRef<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+/// A stored prefix with the `(suffix, value)` pairs of its keys; see
+/// [`HotTree::prefix_groups`].
+pub type PrefixGroup<'a, V> = (PrefixRef<'a>, Vec<(Cow<'a, [u8]>, &'a V)>);
+
+impl std::fmt::Debug for PrefixRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Iterator over `(prefix, suffix, value)` without materializing full keys.
+///
+/// The suffix borrows from the leaf, except for keys with an interned tail (see
+/// [`HotTree::with_suffix_interning`]), whose inline bytes and tail are joined.
+pub struct IterParts<'a, V, S = FnvBuildHasher> {
+    walk: LeafWalk<'a, V, S>,
+}
+
+impl<'a, V, S: BuildHasher> Iterator for IterParts<'a, V, S> {
+    type Item = (PrefixRef<'a>, Cow<'a, [u8]>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (leaf_off, value) = self.walk.next_leaf()?;
+        let leaf = self.walk.tree.leaf_key(leaf_off);
+        let suffix = if leaf.tail.is_empty() {
+            Cow::Borrowed(leaf.mid)
+        } else {
+            Cow::Owned([leaf.mid, leaf.tail].concat())
+        };
+        Some((PrefixRef(leaf.prefix), suffix, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.insert(b"hello", 1);
+        t.insert(b"world", 2);
+        assert_eq!(t.get(b"hello"), Some(&1));
+        assert_eq!(t.get(b"world"), Some(&2));
+        assert_eq!(t.get(b"missing"), None);
+        assert_eq!(t.len(), 2);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut t: HotTree<u64> = HotTree::new();
+        assert_eq!(t.insert(b"key", 1), None);
+        assert_eq!(t.insert(b"key", 2), Some(1));
+        assert_eq!(t.get(b"key"), Some(&2));
+        assert_eq!(t.len(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.insert(b"a", 1);
+        t.insert(b"b", 2);
+        t.insert(b"c", 3);
+
+        assert_eq!(t.remove(b"b"), Some(2));
+        assert_eq!(t.get(b"b"), None);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t.get(b"a"), Some(&1));
+        assert_eq!(t.get(b"c"), Some(&3));
+
+        // Reinserting a removed key should increase length.
+        assert_eq!(t.insert(b"b", 4), None);
+        assert_eq!(t.get(b"b"), Some(&4));
+        assert_eq!(t.len(), 3);
+    }
+
+    #[test]
+    fn test_zst_remove_and_reinsert() {
+        let mut t: HotTree<()> = HotTree::new();
+        assert_eq!(t.insert(b"a", ()), None);
+        assert_eq!(t.insert(b"b", ()), None);
+        assert_eq!(t.insert(b"c", ()), None);
+        assert_eq!(t.len(), 3);
+        assert_eq!(t.get(b"a"), Some(&()));
+        assert_eq!(t.get(b"b"), Some(&()));
+        assert_eq!(t.get(b"c"), Some(&()));
+
+        assert_eq!(t.remove(b"b"), Some(()));
+        assert_eq!(t.get(b"b"), None);
+        assert_eq!(t.get(b"a"), Some(&()));
+        assert_eq!(t.get(b"c"), Some(&()));
+        assert_eq!(t.len(), 2);
+
+        // Reinsertion should clear the tombstone.
+        assert_eq!(t.insert(b"b", ()), None);
+        assert_eq!(t.get(b"b"), Some(&()));
+        assert_eq!(t.len(), 3);
+
+        // Updating an existing key should return Some(()) and keep length stable.
+        assert_eq!(t.insert(b"b", ()), Some(()));
+        assert_eq!(t.len(), 3);
+    }
+
+    #[test]
+    fn test_many() {
+        let mut t: HotTree<u64> = HotTree::new();
+        for i in 0..1000u64 {
+            let key = format!("key{:05}", i);
+            t.insert(key.as_bytes(), i);
+        }
+        assert_eq!(t.len(), 1000);
+        for i in 0..1000u64 {
+            let key = format!("key{:05}", i);
+            assert_eq!(t.get(key.as_bytes()), Some(&i), "Failed at {}", i);
+        }
+    }
+
+    #[test]
+    fn test_prefix_compression() {
+        let mut t: HotTree<u64> = HotTree::new();
+        // URLs with shared prefix
+        t.insert(b"https://example.com/page1", 1);
+        t.insert(b"https://example.com/page2", 2);
+        t.insert(b"https://example.com/page3", 3);
+        t.insert(b"https://other.com/page1", 4);
+
+        assert_eq!(t.get(b"https://example.com/page1"), Some(&1));
+        assert_eq!(t.get(b"https://example.com/page2"), Some(&2));
+        assert_eq!(t.get(b"https://example.com/page3"), Some(&3));
+        assert_eq!(t.get(b"https://other.com/page1"), Some(&4));
+
+        // Check that prefixes were learned
+        assert!(t.prefix_offsets.len() > 1, "Should have learned prefixes");
+    }
+
+    #[test]
+    fn test_prefix_compression_keyed_hasher() {
+        use std::collections::hash_map::RandomState;
+
+        let mut t: HotTree<u64, RandomState> = HotTree::with_hasher(RandomState::new());
+        t.insert(b"https://example.com/page1", 1);
+        t.insert(b"https://example.com/page2", 2);
+        t.insert(b"https://other.com/page1", 3);
+        t.insert(b"ftp://files.example.com/a", 4);
+
+        assert_eq!(t.get(b"https://example.com/page1"), Some(&1));
+        assert_eq!(t.get(b"https://example.com/page2"), Some(&2));
+        assert_eq!(t.get(b"https://other.com/page1"), Some(&3));
+        assert_eq!(t.get(b"ftp://files.example.com/a"), Some(&4));
+
+        // "https:" and "ftp:" on top of the empty prefix.
+        assert_eq!(t.prefix_offsets.len(), 3);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.insert(b"b", 2);
+        t.insert(b"a", 1);
+        t.insert(b"c", 3);
+
+        let pairs: Vec<_> = t.iter().collect();
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[0], (b"a".to_vec(), &1));
+        assert_eq!(pairs[1], (b"b".to_vec(), &2));
+        assert_eq!(pairs[2], (b"c".to_vec(), &3));
+    }
+
+    #[test]
+    fn test_iter_parts() {
+        let mut t: HotTree<u64> = HotTree::new();
+        let keys: [&[u8]; 6] = [
+            b"https://a.example/x",
+            b"https://b.example/y",
+            b"ftp://c.example",
+            b"abc",
+            b"",
+            b"https:",
+        ];
+        for (i, k) in keys.iter().enumerate() {
+            t.insert(k, i as u64);
+        }
+
+        let rebuilt: Vec<(Vec<u8>, &u64)> = t
+            .iter_parts()
+            .map(|(prefix, suffix, v)| ([&*prefix, &*suffix].concat(), v))
+            .collect();
+        let expected: Vec<(Vec<u8>, &u64)> = t.iter().collect();
+        assert_eq!(rebuilt, expected);
+        assert!(t
+            .iter_parts()
+            .any(|(prefix, _, _)| prefix.as_bytes() == b"https:"));
+    }
+
+    #[test]
+    fn test_try_insert_key_too_long() {
+        let mut t: HotTree<u64> = HotTree::new().with_max_key_len(16);
+        assert_eq!(t.try_insert([b'a'; 16], 1), Ok(None));
+        assert_eq!(
+            t.try_insert([b'a'; 17], 2),
+            Err(HotError::KeyTooLong { len: 17, max: 16 })
+        );
+        assert_eq!(t.len(), 1);
+
+        let mut t: HotTree<u64> = HotTree::new();
+        let long = vec![b'x'; MAX_KEY_LEN + 1];
+        assert!(matches!(
+            t.try_insert(&long, 1),
+            Err(HotError::KeyTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn test_try_insert_height_limit() {
+        let mut t: HotTree<u64> = HotTree::new().with_max_height(2);
+        let mut inserted = Vec::new();
+        let mut err = None;
+        // Long keys sharing a common prefix and differing only near the end.
+        for i in 0u32..100_000 {
+            let mut key = vec![b'p'; 200];
+            key.extend_from_slice(&i.to_be_bytes());
+            key.push(b'!');
+            match t.try_insert(&key, i as u64) {
+                Ok(None) => inserted.push(key),
+                Ok(Some(_)) => unreachable!(),
+                Err(e) => {
+                    err = Some((e, key));
+                    break;
+                }
+            }
+        }
+        let (e, rejected) = err.expect("height limit must be reached");
+        assert_eq!(e, HotError::HeightLimit { max: 2 });
+        assert_eq!(t.get(&rejected), None);
+        assert_eq!(t.len(), inserted.len());
+        assert!(t.ptr_height(t.root) <= 2);
+
+        // Updating an existing key is still allowed at the limit.
+        assert_eq!(t.try_insert(&inserted[0], 7), Ok(Some(0)));
+        for (i, k) in inserted.iter().enumerate().skip(1) {
+            assert_eq!(t.get(k), Some(&(i as u64)));
+        }
+    }
+
+    #[test]
+    fn test_keys_differing_by_trailing_zeros_coexist() {
+        let keys: [&[u8]; 6] = [b"", b"\x00", b"\x00\x00", b"a", b"a\x00", b"a\x00\x00"];
+        let mut t: HotTree<u64> = HotTree::new();
+        for (i, key) in keys.iter().enumerate().rev() {
+            assert_eq!(t.insert(key, i as u64), None);
+        }
+        assert_eq!(t.len(), keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(t.get(key), Some(&(i as u64)), "{key:?}");
+        }
+        assert_eq!(t.get(b"\x00\x00\x00"), None);
+        assert!(t.iter().map(|(k, _)| k).eq(keys.iter().map(|k| k.to_vec())));
+        assert_eq!(
+            t.range(&b"a\x00"[..]..).next(),
+            Some((b"a\x00".to_vec(), &4))
+        );
+
+        let long = vec![0u8; MAX_KEY_LEN];
+        t.insert(&long, 9);
+        t.insert(&long[..MAX_KEY_LEN - 1], 8);
+        assert_eq!(t.get(&long), Some(&9));
+        assert_eq!(t.get(&long[..MAX_KEY_LEN - 1]), Some(&8));
+
+        assert_eq!(t.remove(b"\x00"), Some(1));
+        assert_eq!(t.get(b""), Some(&0));
+        assert_eq!(t.get(b"\x00\x00"), Some(&2));
+    }
+
+    #[test]
+    fn test_build_sorted() {
+        let keys: Vec<String> = (0..1000).map(|i| format!("key:{i:04}")).collect();
+        let t: HotTree<usize> =
+            HotTree::build_sorted(keys.iter().enumerate().map(|(i, k)| (k, i))).unwrap();
+        assert_eq!(t.len(), 1000);
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(t.get(k.as_bytes()), Some(&i));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly ascending")]
+    fn test_build_sorted_rejects_unsorted() {
+        let _ = HotTree::<u64>::build_sorted([(b"b", 1), (b"a", 2)]);
+    }
+
+    #[test]
+    fn test_suffix_interning() {
+        let keys: Vec<String> = (0..10_000)
+            .map(|i| format!("sessions:{i}:active"))
+            .collect();
+        let mut inline: HotTree<u64> = HotTree::new();
+        let mut interned: HotTree<u64> = HotTree::new().with_suffix_interning(true);
+        for (i, k) in keys.iter().enumerate() {
+            inline.insert(k.as_bytes(), i as u64);
+            interned.insert(k.as_bytes(), i as u64);
+        }
+        interned.insert(b"short", 1);
+        interned.insert(b"sessions:x:idle", 2);
+        interned.remove(b"sessions:7:active");
+
+        assert_eq!(interned.get(b"sessions:42:active"), Some(&42));
+        assert_eq!(interned.get(b"sessions:42:idle"), None);
+        assert_eq!(interned.get(b"sessions:7:active"), None);
+        assert_eq!(interned.get(b"sessions:x:idle"), Some(&2));
+        assert_eq!(interned.len(), 10_001);
+
+        let rebuilt: Vec<Vec<u8>> = interned
+            .iter_parts()
+            .map(|(prefix, suffix, _)| [&*prefix, &*suffix].concat())
+            .collect();
+        let keys: Vec<Vec<u8>> = interned.iter().map(|(k, _)| k).collect();
+        assert_eq!(rebuilt, keys);
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+        inline.shrink_to_fit();
+        interned.shrink_to_fit();
+        assert!(interned.memory_usage() < inline.memory_usage());
+
+        // Tails that never recur stay inline instead of filling the prefix table.
+        let mut inline: HotTree<u64> = HotTree::new();
+        let mut interned: HotTree<u64> = HotTree::new().with_suffix_interning(true);
+        for i in 0..100_000u64 {
+            let key = format!("https://example.com/{}/{i}", i % 50);
+            inline.insert(&key, i);
+            interned.insert(&key, i);
+        }
+        assert!(interned.prefix_offsets.len() < 100);
+        inline.shrink_to_fit();
+        interned.shrink_to_fit();
+        // Little beyond each leaf's 2-byte tail id and the 4 KiB sketch is extra.
+        assert!(interned.memory_usage() <= inline.memory_usage() + 2 * 100_000 + 8192);
+    }
+
+    #[test]
+    fn test_iter_cow() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.insert(b"https://example.com/a", 1);
+        t.insert(b"short", 2);
+        t.insert(b"ftp://example.org", 3);
+        t.insert(b"k", 4);
+
+        let items: Vec<(Cow<'_, [u8]>, &u64)> = t.iter_cow().collect();
+        let expected: Vec<(Vec<u8>, &u64)> = t.iter().collect();
+        assert_eq!(items.len(), expected.len());
+        for ((cow, v), (key, ev)) in items.iter().zip(&expected) {
+            assert_eq!(&**cow, key.as_slice());
+            assert_eq!(v, ev);
+            let prefixed = key.starts_with(b"https:") || key.starts_with(b"ftp:");
+            assert_eq!(matches!(cow, Cow::Owned(_)), prefixed, "{key:?}");
+        }
+    }
+
+    #[test]
+    fn test_dead_bytes() {
+        let mut t: HotTree<u64> = HotTree::new();
+        for i in 0..10_000u64 {
+            t.insert(format!("key:{i:05}").as_bytes(), i);
+        }
+        let baseline = t.dead_bytes();
+
+        for i in (0..10_000u64).step_by(2) {
+            t.remove(format!("key:{i:05}").as_bytes());
+        }
+        let after_remove = t.dead_bytes();
+        // At least the removed leaves (3-byte header + 5-byte suffix + 4-byte index) and
+        // their value slots are dead.
+        let removed_floor = 5_000 * (12 + std::mem::size_of::<Option<u64>>());
+        assert!(after_remove >= baseline + removed_floor);
+
+        t.compact();
+        assert_eq!(t.nodes.free_bytes(), 0);
+        assert!(t.dead_bytes() < after_remove);
+        assert!(t.dead_bytes() >= removed_floor);
+    }
+
+    #[test]
+    fn test_value_index_width() {
+        let mut wide: HotTree<u64> = HotTree::new();
+        let mut narrow: HotTree<u64> = HotTree::new().with_value_index_width(ValueIndexWidth::U16);
+        for i in 0..1000u64 {
+            let key = format!("k{i}");
+            wide.insert(key.as_bytes(), i);
+            narrow.insert(key.as_bytes(), i);
+        }
+        for i in 0..1000u64 {
+            assert_eq!(narrow.get(format!("k{i}").as_bytes()), Some(&i));
+        }
+        wide.shrink_to_fit();
+        narrow.shrink_to_fit();
+        assert_eq!(wide.memory_usage() - narrow.memory_usage(), 2 * 1000);
+    }
+
+    #[test]
+    fn test_value_index_overflow() {
+        let mut t: HotTree<u32> = HotTree::new().with_value_index_width(ValueIndexWidth::U16);
+        for i in 0..=u16::MAX as u32 {
+            t.insert(i.to_be_bytes(), i);
+        }
+        assert_eq!(
+            t.try_insert(b"one more", 0),
+            Err(HotError::ValueIndexOverflow { max: 65536 })
+        );
+        // Updating existing keys still works.
+        assert_eq!(t.try_insert(7u32.to_be_bytes(), 70), Ok(Some(7)));
+        assert_eq!(t.get((u16::MAX as u32).to_be_bytes()), Some(&65535));
+    }
+
+    #[test]
+    fn test_get_sorted_batch() {
+        let mut t: HotTree<u64> = HotTree::new();
+        for (i, k) in ["pear", "apple", "fig", "https://x/1", "https://x/0"]
+            .iter()
+            .enumerate()
+        {
+            t.insert(k.as_bytes(), i as u64);
+        }
+        let batch: [&[u8]; 7] = [
+            b"fig",
+            b"missing",
+            b"https://x/1",
+            b"apple",
+            b"fig",
+            b"https://x/0",
+            b"",
+        ];
+
+        let mut expected: Vec<(Vec<u8>, &u64)> = batch
+            .iter()
+            .filter_map(|k| Some((k.to_vec(), t.get(k)?)))
+            .collect();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(t.get_sorted_batch(&batch), expected);
+        assert_eq!(expected.len(), 4);
+    }
+
+    #[test]
+    fn test_deep_memory_usage() {
+        let mut t: HotTree<String> = HotTree::new();
+        let mut total = 0;
+        for i in 0..100 {
+            let value = "x".repeat(i * 3);
+            total += value.len();
+            t.insert(format!("k{i}").as_bytes(), value);
+        }
+        t.insert(b"gone", "y".repeat(1000));
+        t.remove(b"gone");
+
+        let shallow = t.memory_usage();
+        assert_eq!(t.deep_memory_usage(|s| s.capacity()), shallow + total);
+        assert_eq!(t.deep_memory_usage(|_| 0), shallow);
+    }
+
+    #[test]
+    fn test_contains_prefix() {
+        let mut t: HotTree<u64> = HotTree::new();
+        assert!(!t.contains_prefix(b""));
+        t.insert(b"admin:1", 0);
+        assert!(!t.contains_prefix(b"user:"));
+        t.insert(b"user:42", 1);
+        t.insert(b"userx", 2);
+        t.insert(b"zeta", 3);
+        assert!(t.contains_prefix(b"user:"));
+        assert!(t.contains_prefix(b"user"));
+        assert!(t.contains_prefix(b"user:42"));
+        assert!(!t.contains_prefix(b"user:42:"));
+        assert!(!t.contains_prefix(b"user:5"));
+        assert!(!t.contains_prefix(b"users"));
+        assert!(t.contains_prefix(b""));
+        t.remove(b"user:42");
+        assert!(!t.contains_prefix(b"user:"));
+    }
+
+    #[test]
+    fn test_arena_fragmentation() {
+        let mut t: HotTree<u64> = HotTree::new();
+        assert_eq!(t.arena_fragmentation(), 0.0);
+        for i in 0..20_000u64 {
+            t.insert(format!("https://example.com/{i}").as_bytes(), i);
+        }
+        for i in (0..20_000u64).filter(|i| i % 3 != 0) {
+            t.remove(format!("https://example.com/{i}").as_bytes());
+        }
+
+        let frag = t.arena_fragmentation();
+        assert!(frag > 0.0 && frag < 1.0, "{frag}");
+        let holes: usize = t.arena_free_histogram().iter().sum();
+        let expected: usize = t.nodes.free.iter().map(Vec::len).sum();
+        assert!(holes > 0);
+        assert_eq!(holes, expected);
+
+        t.compact();
+        assert_eq!(t.arena_fragmentation(), 0.0);
+        assert_eq!(t.arena_free_histogram(), [0; ARENA_FREE_BUCKETS]);
+    }
+
+    #[test]
+    fn test_get_verifies_undiscriminated_bytes() {
+        // The only discriminative bit is in the last byte; a lookup differing earlier
+        // still descends to a leaf and must be rejected by the full comparison.
+        let mut t: HotTree<u64> = HotTree::new();
+        t.insert(b"https://example.com/a", 1);
+        t.insert(b"https://example.com/b", 2);
+        assert_eq!(t.get(b"https://exbmple.com/a"), None);
+        assert_eq!(t.get(b"http://example.com/b"), None);
+        assert_eq!(t.get(b"https://example.com/b"), Some(&2));
+    }
+
+    #[test]
+    fn test_handles() {
+        let mut t: HotTree<u64> = HotTree::new();
+        let mut handles = Vec::new();
+        for i in 0..100u64 {
+            let (old, h) = t.insert_with_handle(format!("id:{i}"), i);
+            assert_eq!(old, None);
+            handles.push(h);
+        }
+        for i in 100..2000u64 {
+            t.insert(format!("id:{i}").as_bytes(), i);
+        }
+        t.compact();
+
+        for (i, &h) in handles.iter().enumerate() {
+            assert_eq!(t.get_by_handle(h), Some(&(i as u64)));
+        }
+        *t.get_by_handle_mut(handles[5]).unwrap() = 500;
+        assert_eq!(t.get(b"id:5"), Some(&500));
+
+        // Updating through insert keeps the same handle.
+        let (old, h) = t.insert_with_handle(b"id:7", 70);
+        assert_eq!((old, h), (Some(7), handles[7]));
+
+        t.remove(b"id:9");
+        assert_eq!(t.get_by_handle(handles[9]), None);
+        assert!(t.get_by_handle_mut(handles[9]).is_none());
+        let (_, h9) = t.insert_with_handle(b"id:9", 9);
+        assert_ne!(h9, handles[9]);
+        assert_eq!(t.get_by_handle(handles[9]), None);
+        assert_eq!(t.get_by_handle(h9), Some(&9));
+
+        let mut z: HotTree<()> = HotTree::new();
+        let (_, ha) = z.insert_with_handle(b"a", ());
+        let (_, hb) = z.insert_with_handle(b"b", ());
+        z.remove(b"a");
+        assert_eq!(z.get_by_handle(ha), None);
+        assert_eq!(z.get_by_handle(hb), Some(&()));
+    }
+
+    #[test]
+    fn test_growth_policy_fixed_chunk() {
+        const CHUNK: usize = 64 * 1024;
+        let mut t: HotTree<u64> =
+            HotTree::new().with_growth_policy(GrowthPolicy::FixedChunk(CHUNK));
+        for i in 0..50_000u64 {
+            t.insert(format!("https://example.com/{i}").as_bytes(), i);
+            assert!(t.nodes.data.capacity() - t.nodes.data.len() <= CHUNK);
+            assert!(t.leaves.capacity() - t.leaves.len() <= CHUNK);
+        }
+        t.compact();
+        assert_eq!(t.nodes.growth, GrowthPolicy::FixedChunk(CHUNK));
+        for i in 0..50_000u64 {
+            assert_eq!(
+                t.get(format!("https://example.com/{i}").as_bytes()),
+                Some(&i)
+            );
+        }
+
+        let mut f: HotTree<u64> = HotTree::new().with_growth_policy(GrowthPolicy::Factor(1.25));
+        for i in 0..10_000u64 {
+            f.insert(format!("k{i}").as_bytes(), i);
+        }
+        assert_eq!(f.len(), 10_000);
+        assert!(f.leaves.capacity() <= f.leaves.len() * 5 / 4 + 64);
+    }
+
+    #[test]
+    fn test_growth_policy_reserve_matches_grown_capacity() {
+        // The memory budget predicts growth with `grown_capacity`, so it must describe
+        // exactly what `reserve` does rather than `Vec`'s own heuristics.
+        for policy in [
+            GrowthPolicy::Doubling,
+            GrowthPolicy::FixedChunk(64),
+            GrowthPolicy::Factor(1.5),
+        ] {
+            let mut buf: Vec<u32> = Vec::new();
+            for additional in (0..200).map(|i| i % 7 + 1) {
+                let expected = policy.grown_capacity(buf.len(), buf.capacity(), additional, 4);
+                policy.reserve(&mut buf, additional);
+                assert_eq!(buf.capacity(), expected, "{policy:?}");
+                buf.resize(buf.len() + additional, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_node_tag_histogram() {
+        let mut t: HotTree<u64> = HotTree::new();
+        assert!(t.node_tag_histogram().is_empty());
+
+        // Each key differs from the others at a byte 0..32 positions apart, so compound
+        // nodes must gather discriminative bits from more than 8 distinct bytes.
+        for i in 0..32usize {
+            for j in 0..4u8 {
+                let mut key = vec![b'a'; 40];
+                key[i] = b'b';
+                key[39] = j;
+                t.insert(&key, (i * 4) as u64 + j as u64);
+            }
+        }
+        let hist = t.node_tag_histogram();
+        assert!(hist.keys().any(|k| k.starts_with("MultiMask")), "{hist:?}");
+    }
+
+    #[test]
+    fn test_inline_values() {
+        let mut indexed: HotTree<u32> = HotTree::new();
+        let mut inline: HotTree<u32> = HotTree::new().with_inline_values();
+        let mut model = BTreeMap::new();
+        for i in 0..20_000u32 {
+            let key = format!("https://example.com/{}/{i}", i % 97);
+            assert_eq!(indexed.insert(key.as_bytes(), i), None);
+            assert_eq!(inline.insert(key.as_bytes(), i), None);
+            model.insert(key.into_bytes(), i);
+        }
+        for i in (0..20_000u32).step_by(3) {
+            let key = format!("https://example.com/{}/{i}", i % 97);
+            assert_eq!(inline.insert(key.as_bytes(), i * 2), Some(i));
+            model.insert(key.into_bytes(), i * 2);
+        }
+        for i in (0..20_000u32).step_by(5) {
+            let key = format!("https://example.com/{}/{i}", i % 97);
+            assert_eq!(inline.remove(key.as_bytes()), model.remove(key.as_bytes()));
+        }
+        *inline.get_mut(b"https://example.com/1/1").unwrap() += 1;
+        *model.get_mut(&b"https://example.com/1/1"[..]).unwrap() += 1;
+
+        assert_eq!(inline.len(), model.len());
+        for (k, v) in &model {
+            assert_eq!(inline.get(k), Some(v));
+        }
+        let got: Vec<(Vec<u8>, u32)> = inline.iter().map(|(k, v)| (k, *v)).collect();
+        let expected: Vec<(Vec<u8>, u32)> = model.into_iter().collect();
+        assert_eq!(got, expected);
+
+        let (_, h) = inline.insert_with_handle(b"handle", 7);
+        *inline.get_by_handle_mut(h).unwrap() = 8;
+        assert_eq!(inline.get_by_handle(h), Some(&8));
+        inline.remove(b"handle");
+        assert_eq!(inline.get_by_handle(h), None);
+
+        indexed.shrink_to_fit();
+        inline.shrink_to_fit();
+        assert!(inline.memory_usage() < indexed.memory_usage());
+
+        let mut rgb: HotTree<[u8; 3]> = HotTree::new().with_inline_values();
+        rgb.insert(b"colour:teal", [0, 128, 128]);
+        rgb.insert(b"colour:navy", [0, 0, 128]);
+        assert_eq!(rgb.get(b"colour:teal"), Some(&[0, 128, 128]));
+        assert_eq!(rgb.remove(b"colour:navy"), Some([0, 0, 128]));
+    }
+
+    #[test]
+    fn test_drain_below_watermark() {
+        let keys: Vec<Vec<u8>> = (0..20_000u64)
+            .map(|i| format!("event/{i:08}").into_bytes())
+            .collect();
+        let mut t: HotTree<u64> = HotTree::new();
+        let mut drained = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            t.insert(key, i as u64);
+            if i % 1000 == 999 {
+                let watermark = &keys[i - 500];
+                let batch: Vec<(Vec<u8>, u64)> = t.drain_below(watermark).collect();
+                assert!(batch.windows(2).all(|w| w[0].0 < w[1].0));
+                assert!(batch.iter().all(|(k, _)| k < watermark));
+                assert!(t.iter().all(|(k, _)| k >= *watermark));
+                drained.extend(batch);
+            }
+        }
+        assert_eq!(t.drain_below(b"event/").count(), 0);
+
+        let remaining = t.len();
+        drained.extend(t.iter().map(|(k, v)| (k, *v)));
+        assert_eq!(drained.len(), keys.len());
+        assert_eq!(remaining, 501);
+        for (i, (k, v)) in drained.into_iter().enumerate() {
+            assert_eq!(k, keys[i]);
+            assert_eq!(v, i as u64);
+        }
+
+        assert_eq!(t.drain_below(b"zzz").count(), 501);
+        assert!(t.is_empty());
+        assert!(t.root.is_null());
+    }
+
+    #[test]
+    fn test_key_length_stats() {
+        let mut t: HotTree<u64> = HotTree::new();
+        assert_eq!(t.key_length_stats(), (0, 0, 0.0));
+
+        // 100 keys each of lengths 24..=27, all sharing a learnable prefix.
+        let mut keys = Vec::new();
+        for i in 0..400u64 {
+            let key = format!("https://example.com/{:0w$}", i, w = 4 + (i % 4) as usize);
+            t.insert(key.as_bytes(), i);
+            keys.push(key.into_bytes());
+        }
+        assert_eq!(t.key_length_stats(), (24, 27, 25.5));
+
+        let hist = encoding::key_length_histogram(keys);
+        assert_eq!(hist.len(), 28);
+        assert_eq!(&hist[24..], &[100, 100, 100, 100]);
+        assert_eq!(hist[..24].iter().sum::<usize>(), 0);
+    }
+
+    #[test]
+    fn test_values_mut() {
+        let mut t: HotTree<u64> = HotTree::new();
+        for i in 0..5000u64 {
+            t.insert(format!("counter/{i}").as_bytes(), i);
+        }
+        for i in (0..5000u64).step_by(7) {
+            t.remove(format!("counter/{i}").as_bytes());
+        }
+        assert_eq!(t.values_mut().len(), 5000);
+        assert_eq!(t.values_mut().iter().flatten().count(), t.len());
+
+        for v in t.values_mut().iter_mut().flatten() {
+            *v *= 2;
+        }
+        for i in 0..5000u64 {
+            let expected = (i % 7 != 0).then_some(i * 2);
+            assert_eq!(t.get(format!("counter/{i}").as_bytes()).copied(), expected);
+        }
+
+        let mut set: HotTree<()> = HotTree::new();
+        set.insert(b"a", ());
+        assert!(set.values_mut().is_empty());
+    }
+
+    #[test]
+    fn test_iter_insertion_order() {
+        fn check<V: Copy + PartialEq + std::fmt::Debug>(
+            mut t: HotTree<V>,
+            value: impl Fn(u32) -> V,
+        ) {
+            let mut order: Vec<u32> = (0..3000u32).map(|i| (i * 7919) % 3000).collect();
+            for &i in &order {
+                t.insert(format!("wal/{i}").as_bytes(), value(i));
+            }
+            // Updates keep their position; removed keys vanish, and re-inserts go last.
+            t.insert(b"wal/5", value(5));
+            for i in (0..3000u32).step_by(10) {
+                t.remove(format!("wal/{i}").as_bytes());
+            }
+            t.insert(b"wal/20", value(20));
+            order.retain(|i| i % 10 != 0);
+            order.push(20);
+
+            let got: Vec<(Vec<u8>, V)> = t.iter_insertion_order().map(|(k, v)| (k, *v)).collect();
+            let expected: Vec<(Vec<u8>, V)> = order
+                .iter()
+                .map(|&i| (format!("wal/{i}").into_bytes(), value(i)))
+                .collect();
+            assert_eq!(got, expected);
+        }
+
+        check(HotTree::<u32>::new(), |i| i);
+        check(HotTree::<u32>::new().with_inline_values(), |i| i);
+        check(HotTree::<u32>::new().with_suffix_interning(true), |i| i);
+        check(HotTree::<()>::new(), |_| ());
+    }
+
+    #[test]
+    fn test_key_argument_types() {
+        let mut t: HotTree<u64> = HotTree::new();
+        let owned = String::from("string");
+        let bytes = b"vec".to_vec();
+        t.insert("str", 1);
+        t.insert(owned.clone(), 2);
+        t.insert(&bytes, 3);
+        t.insert(&b"slice"[..], 4);
+        assert_eq!(t.try_insert(b"array", 5), Ok(None));
+
+        assert_eq!(t.get("str"), Some(&1));
+        assert_eq!(t.get(&owned), Some(&2));
+        assert_eq!(t.get(bytes.clone()), Some(&3));
+        assert_eq!(t.get(&b"slice"[..]), Some(&4));
+        assert!(t.contains_key("array"));
+        *t.get_mut(String::from("str")).unwrap() += 10;
+        assert_eq!(t.get(b"str"), Some(&11));
+        assert_eq!(t.remove(owned), Some(2));
+        assert_eq!(t.len(), 4);
+    }
+
+    #[test]
+    fn test_arena_limit_errors_instead_of_wrapping() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.config.arena_limit = 16 * 1024;
+        let mut inserted = 0u64;
+        let err = loop {
+            match t.try_insert(format!("document/{inserted:06}"), inserted) {
+                Ok(None) => inserted += 1,
+                Ok(Some(_)) => unreachable!(),
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(err, HotError::ArenaFull { max: 16 * 1024 });
+        assert!(inserted > 0);
+        assert!(t.leaves.len() <= 16 * 1024 && t.nodes.data.len() <= 16 * 1024);
+
+        // The tree is untouched by the failed insert, and updates still succeed.
+        assert_eq!(t.len() as u64, inserted);
+        assert_eq!(t.try_insert("document/000000", 7), Ok(Some(0)));
+        for i in 1..inserted {
+            assert_eq!(t.get(format!("document/{i:06}")), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_node_arena_near_offset_limit_errors() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.config.arena_limit = 1 << 20;
+        // Simulate a node arena whose next offset sits just under the limit; leaves stay tiny.
+        t.nodes.data.resize((1 << 20) - 4 * MAX_NODE_SIZE, 0);
+        let mut inserted = 0u64;
+        let err = loop {
+            match t.try_insert(inserted.to_be_bytes(), inserted) {
+                Ok(None) => inserted += 1,
+                Ok(Some(_)) => unreachable!(),
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(err, HotError::ArenaFull { max: 1 << 20 });
+        assert!(t.leaves.len() < 1024);
+        assert!(t.nodes.data.len() <= 1 << 20);
+        for i in 0..inserted {
+            assert_eq!(t.get(i.to_be_bytes()), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_compact_leaves_after_churn() {
+        fn churn<V: Copy + PartialEq + std::fmt::Debug>(
+            mut t: HotTree<V>,
+            value: impl Fn(u32) -> V,
+        ) {
+            let key = |i: u32| format!("session/{i:05}");
+            for i in 0..2000 {
+                t.insert(key(i), value(i));
+            }
+            let steady = t.leaves.len();
+            for round in 0..20u32 {
+                for i in (round % 4..2000).step_by(4) {
+                    t.remove(key(i));
+                }
+                for i in (round % 4..2000).step_by(4) {
+                    t.insert(key(i), value(i));
+                }
+            }
+            let (_, handle) = t.insert_with_handle(key(1).as_bytes(), value(1));
+            assert!(t.leaves.len() > 5 * steady);
+
+            let reclaimed = t.compact_leaves();
+            assert_eq!(t.leaves.len(), steady);
+            assert!(reclaimed > 4 * steady);
+            assert_eq!(t.dead_bytes(), 0);
+            assert_eq!(t.get_by_handle(handle), None);
+
+            assert_eq!(t.len(), 2000);
+            for i in 0..2000 {
+                assert_eq!(t.get(key(i)), Some(&value(i)));
+            }
+            let keys: Vec<Vec<u8>> = t.iter_insertion_order().map(|(k, _)| k).collect();
+            assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+            // The rebuilt tree keeps working.
+            t.remove(key(0));
+            t.insert(key(5000), value(5000));
+            assert_eq!(t.get(key(5000)), Some(&value(5000)));
+            assert_eq!(t.iter().count(), 2000);
+        }
+
+        churn(HotTree::<u64>::new(), u64::from);
+        churn(HotTree::<u32>::new().with_inline_values(), |i| i);
+        churn(
+            HotTree::<u32>::new().with_value_index_width(ValueIndexWidth::U16),
+            |i| i,
+        );
+        churn(HotTree::<u64>::new().with_suffix_interning(true), u64::from);
+        churn(HotTree::<()>::new(), |_| ());
+    }
+
+    #[test]
+    fn test_debug_entries() {
+        let mut t: HotTree<u64> = HotTree::new();
+        for i in 0..300u64 {
+            t.insert(format!("https://example.com/item/{i}"), i);
+        }
+        for i in (0..300u64).step_by(3) {
+            t.remove(format!("https://example.com/item/{i}"));
+        }
+
+        let entries = t.debug_entries();
+        assert_eq!(entries.len(), 300);
+        let live: Vec<&LeafDebug> = entries.iter().filter(|e| !e.is_tombstone).collect();
+        assert_eq!(live.len(), t.len());
+        for e in &live {
+            let value = t.get(&e.key).expect("live entry must be reachable");
+            assert_eq!(e.value_idx, Some(*value as usize));
+        }
+        assert!(entries.iter().any(|e| e.prefix_id != 0));
+        assert!(entries
+            .iter()
+            .all(|e| e.suffix_len < e.key.len() || e.prefix_id == 0));
+        assert!(entries
+            .iter()
+            .filter(|e| e.is_tombstone)
+            .all(|e| t.get(&e.key).is_none()));
+    }
+
+    #[test]
+    fn test_shared_prefix_pool() {
+        let tenant_keys = |tenant: u32| -> Vec<String> {
+            (0..2000u32)
+                .map(|i| format!("site{}.example.com/{tenant}/{i}", i % 200))
+                .collect()
+        };
+        let (keys_a, keys_b) = (tenant_keys(1), tenant_keys(2));
+        let pool = Arc::new(SharedPrefixPool::from_keys(&keys_a));
+        assert_eq!(pool.len(), 200);
+
+        let mut a: HotTree<u32> = HotTree::new_with_pool(pool.clone());
+        let mut b: HotTree<u32> = HotTree::new_with_pool(pool.clone());
+        let mut own_a: HotTree<u32> = HotTree::new();
+        let mut own_b: HotTree<u32> = HotTree::new();
+        for (i, (ka, kb)) in keys_a.iter().zip(&keys_b).enumerate() {
+            a.insert(ka, i as u32);
+            b.insert(kb, i as u32);
+            own_a.insert(ka, i as u32);
+            own_b.insert(kb, i as u32);
+        }
+        // Prefixes learned locally after the shared ones get ids past the pool.
+        a.insert("mirror.example.org/file", 9999);
+
+        for (i, (ka, kb)) in keys_a.iter().zip(&keys_b).enumerate() {
+            assert_eq!(a.get(ka), Some(&(i as u32)));
+            assert_eq!(b.get(kb), Some(&(i as u32)));
+        }
+        assert_eq!(a.get("mirror.example.org/file"), Some(&9999));
+        let mut expected: Vec<Vec<u8>> = keys_a.iter().map(|k| k.clone().into_bytes()).collect();
+        expected.push(b"mirror.example.org/file".to_vec());
+        expected.sort();
+        assert!(a.iter().map(|(k, _)| k).eq(expected));
+
+        // Tenant b only uses shared prefixes; a learned one of its own.
+        assert_eq!(b.prefix_pool.len(), 0);
+        assert_eq!(a.prefix_pool.len(), "mirror.example.org/".len());
+        let table_bytes = |t: &HotTree<u32>| {
+            t.prefix_pool.capacity()
+                + t.prefix_offsets.capacity() * 4
+                + t.prefix_hash.capacity() * 16
+        };
+        assert!(
+            pool.memory_usage() + table_bytes(&a) + table_bytes(&b)
+                < table_bytes(&own_a) + table_bytes(&own_b)
+        );
+    }
+
+    #[test]
+    fn test_first_with_prefix() {
+        let mut t: HotTree<u64> = HotTree::new();
+        for (i, key) in [
+            "user:zed",
+            "user:bob",
+            "users",
+            "user:alice",
+            "order:1",
+            "user;",
+        ]
+        .iter()
+        .enumerate()
+        {
+            t.insert(key, i as u64);
+        }
+        assert_eq!(
+            t.first_with_prefix(b"user:"),
+            Some((b"user:alice".to_vec(), &3))
+        );
+        assert_eq!(
+            t.first_with_prefix(b"user"),
+            Some((b"user:alice".to_vec(), &3))
+        );
+        assert_eq!(t.first_with_prefix(b"users"), Some((b"users".to_vec(), &2)));
+        assert_eq!(t.first_with_prefix(b""), Some((b"order:1".to_vec(), &4)));
+        assert_eq!(t.first_with_prefix(b"user:c"), None);
+        assert_eq!(t.first_with_prefix(b"zzz"), None);
+        assert_eq!(HotTree::<u64>::new().first_with_prefix(b"user:"), None);
+    }
+
+    #[test]
+    fn test_remove_by_handle() {
+        fn check<V: Copy + PartialEq + std::fmt::Debug>(
+            mut t: HotTree<V>,
+            value: impl Fn(u32) -> V,
+        ) {
+            let handles: Vec<Handle> = (0..1000u32)
+                .map(|i| {
+                    t.insert_with_handle(format!("entity/{i}").as_bytes(), value(i))
+                        .1
+                })
+                .collect();
+            for i in (0..1000u32).filter(|i| i % 3 == 0) {
+                assert_eq!(t.remove_by_handle(handles[i as usize]), Some(value(i)));
+                assert_eq!(t.remove_by_handle(handles[i as usize]), None);
+            }
+            // A handle outlives a re-insert of its key only as a dead handle.
+            t.insert(b"entity/0", value(0));
+            assert_eq!(t.remove_by_handle(handles[0]), None);
+
+            assert_eq!(t.len(), 1000 - 334 + 1);
+            for i in 0..1000u32 {
+                let expected = (i % 3 != 0 || i == 0).then(|| value(i));
+                assert_eq!(t.get(format!("entity/{i}")).copied(), expected);
+            }
+        }
+
+        check(HotTree::<u64>::new(), u64::from);
+        check(HotTree::<u32>::new().with_inline_values(), |i| i);
+        check(HotTree::<()>::new(), |_| ());
+    }
+
+    #[test]
+    fn test_bloom_short_circuits_absent_keys() {
+        let mut t: HotTree<u64> = HotTree::new();
+        for i in 0..10_000u64 {
+            t.insert(format!("https://example.com/item/{i}"), i);
+        }
+        t.enable_bloom(100_000, 0.01);
+        assert_eq!(t.bloom_false_positive_rate().map(|p| p < 1e-6), Some(true));
+        for i in 10_000..100_000u64 {
+            t.insert(format!("https://example.com/item/{i}"), i);
+        }
+        let bits = t.bloom_bits().unwrap();
+        assert!((900_000..1_000_000).contains(&bits), "{bits}");
+        let fp_rate = t.bloom_false_positive_rate().unwrap();
+        assert!((0.005..0.015).contains(&fp_rate), "{fp_rate}");
+
+        for i in 0..100_000u64 {
+            assert_eq!(t.get(format!("https://example.com/item/{i}")), Some(&i));
+        }
+
+        let skips_before = BLOOM_SKIPS.with(|skips| skips.get());
+        for i in 0..100_000u64 {
+            assert!(!t.contains_key(format!("https://example.com/missing/{i}")));
+        }
+        let short_circuited = BLOOM_SKIPS.with(|skips| skips.get()) - skips_before;
+        assert!(short_circuited > 98_000, "{short_circuited}");
+
+        // Removed keys become stale positives but still miss.
+        t.remove("https://example.com/item/7");
+        assert_eq!(t.get("https://example.com/item/7"), None);
+        assert!(t.memory_usage() > bits / 8);
+    }
+
+    #[test]
+    fn test_insert_and_get_mut() {
+        let mut t: HotTree<Vec<u32>> = HotTree::new();
+        for i in 0..1000u32 {
+            t.insert_and_get_mut(format!("key/{i}"), Vec::new()).push(i);
+        }
+        let v = t.insert_and_get_mut("key/7", vec![70]);
+        assert_eq!(v, &[70]);
+        v.extend([71, 72]);
+        assert_eq!(t.get("key/7"), Some(&vec![70, 71, 72]));
+        assert_eq!(t.get("key/999"), Some(&vec![999]));
+        assert_eq!(t.len(), 1000);
+
+        let mut inline: HotTree<u32> = HotTree::new().with_inline_values();
+        *inline.insert_and_get_mut("a", 1) += 10;
+        *inline.insert_and_get_mut("b", 2) += 20;
+        assert_eq!(inline.get("a"), Some(&11));
+        assert_eq!(inline.get("b"), Some(&22));
+    }
+
+    #[test]
+    fn test_memory_budget_rejects_inserts() {
+        let budget = 256 * 1024;
+        let mut t: HotTree<u64> = HotTree::new().with_memory_budget(budget);
+        let mut stored = 0u64;
+        let err = loop {
+            match t.try_insert(
+                format!("https://example.com/{}/item/{stored}", stored % 97),
+                stored,
+            ) {
+                Ok(None) => stored += 1,
+                Ok(Some(_)) => unreachable!(),
+                Err(e) => break e,
+            }
+            assert!(t.memory_usage() <= budget);
+        };
+        assert_eq!(err, HotError::MemoryBudget { max: budget });
+        assert!(stored > 1000, "{stored}");
+        assert_eq!(t.len() as u64, stored);
+        assert!(t.memory_usage() <= budget);
+        for i in 0..stored {
+            assert_eq!(
+                t.get(format!("https://example.com/{}/item/{i}", i % 97)),
+                Some(&i)
+            );
+        }
+
+        // Updating a stored key allocates nothing and is still allowed.
+        assert_eq!(t.try_insert("https://example.com/0/item/0", 7), Ok(Some(0)));
+        assert_eq!(t.get("https://example.com/0/item/0"), Some(&7));
+    }
+
+    #[test]
+    fn test_iter_into_reuses_buffer() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..5000u32 {
+            t.insert(format!("https://example.com/{}/{i}", i % 13), i);
+        }
+        let mut buf = Vec::new();
+        let mut entries = Vec::new();
+        t.iter_into(&mut buf, |k, v| entries.push((k.to_vec(), *v)));
+        assert!(entries.iter().map(|(k, v)| (k.clone(), v)).eq(t.iter()));
+
+        let capacity = buf.capacity();
+        let ptr = buf.as_ptr();
+        let mut count = 0;
+        t.iter_into(&mut buf, |_, _| count += 1);
+        assert_eq!(count, 5000);
+        assert_eq!((buf.capacity(), buf.as_ptr()), (capacity, ptr));
+    }
+
+    #[test]
+    fn test_contains_key_skips_removed_slots() {
+        let key = |i: u32| format!("https://example.com/{}/{}", "x".repeat(200), i);
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..1000 {
+            t.insert(key(i), i);
+        }
+        for i in (0..1000).step_by(3) {
+            t.remove(key(i));
+        }
+        let removed_slots = t.values.iter().filter(|v| v.is_none()).count();
+        assert_eq!(removed_slots, 334);
+        for i in 0..1000 {
+            assert_eq!(t.contains_key(key(i)), i % 3 != 0, "key {i}");
+            assert_eq!(t.contains_key(key(i)), t.get(key(i)).is_some());
+        }
+
+        // Re-inserting a removed key takes a new slot; the old `None` slot stays unreachable.
+        t.insert(key(0), 7);
+        assert!(t.contains_key(key(0)));
+        assert_eq!(
+            t.values.iter().filter(|v| v.is_none()).count(),
+            removed_slots
+        );
+    }
+
+    #[test]
+    fn test_iter_from_resumes_checkpoint() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..2000u32 {
+            t.insert(format!("export/{}/{i:05}", i % 11), i);
+        }
+        let all: Vec<(Vec<u8>, u32)> = t.iter().map(|(k, v)| (k, *v)).collect();
+
+        let first: Vec<(Vec<u8>, u32)> = all[..1000].to_vec();
+        let checkpoint = first.last().unwrap().0.clone();
+        let resume = |t: &HotTree<u32>| -> Vec<(Vec<u8>, u32)> {
+            t.iter_from(&checkpoint)
+                .skip_while(|(k, _)| *k == checkpoint)
+                .map(|(k, v)| (k, *v))
+                .collect()
+        };
+        assert_eq!([first.clone(), resume(&t)].concat(), all);
+
+        // The checkpoint key is removed between runs: resume at its successor.
+        t.remove(&checkpoint);
+        assert_eq!(resume(&t), all[1000..]);
+        assert_eq!(t.iter_from(&checkpoint).next().unwrap().0, all[1000].0);
+
+        assert_eq!(t.iter_from(b"").count(), 1999);
+        assert_eq!(t.iter_from(b"zzz").next(), None);
+    }
+
+    #[test]
+    fn test_structure_digest() {
+        let build = |n: u32| {
+            let mut t: HotTree<u32> = HotTree::new();
+            for i in 0..n {
+                t.insert(format!("https://example.com/{}/{i}", i % 17), i);
+            }
+            t
+        };
+        let mut t = build(5000);
+        let digest = t.structure_digest();
+        assert_eq!(build(5000).structure_digest(), digest);
+        assert_eq!(
+            HotTree::<u32>::new().structure_digest(),
+            FnvHasher::default().finish()
+        );
+
+        // Churn leaves holes in the node arena; compacting moves every node but keeps the shape.
+        for i in 0..5000 {
+            t.remove(format!("https://example.com/{}/{i}", i % 17));
+            t.insert(format!("https://example.com/{}/{i}", i % 17), i);
+        }
+        let churned = t.structure_digest();
+        assert!(t.compact() > 0);
+        assert_eq!(t.structure_digest(), churned);
+
+        // Values are not part of the shape; keys are.
+        t.insert("https://example.com/0/0", 99);
+        assert_eq!(t.structure_digest(), churned);
+        t.insert("https://example.com/new", 1);
+        assert_ne!(t.structure_digest(), churned);
+        assert_ne!(build(4999).structure_digest(), digest);
+    }
 
-impl<V: std::fmt::Debug + Clone> std::fmt::Debug for HotTree<V> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_map().entries(self.iter()).finish()
+    #[test]
+    fn test_iter_by_depth() {
+        let mut t: HotTree<u32> = HotTree::new();
+        assert_eq!(t.iter_by_depth().count(), 0);
+        t.insert("only", 0);
+        assert_eq!(t.height(), 0);
+        assert_eq!(
+            t.iter_by_depth().map(|(_, _, d)| d).collect::<Vec<_>>(),
+            [0]
+        );
+
+        // A few short keys near the root, and a dense cluster sharing a long prefix.
+        for (i, k) in ["a", "m", "z"].iter().enumerate() {
+            t.insert(k, i as u32);
+        }
+        for i in 0..5000u32 {
+            t.insert(format!("m/cluster/{i:05}"), i);
+        }
+        let entries: Vec<(Vec<u8>, &u32, u8)> = t.iter_by_depth().collect();
+        assert!(entries.iter().map(|(k, v, _)| (k.clone(), *v)).eq(t.iter()));
+        assert!(entries.iter().all(|&(_, _, d)| d >= 1 && d <= t.height()));
+        assert!(entries.iter().any(|&(_, _, d)| d == t.height()));
+        let depth_of = |key: &[u8]| entries.iter().find(|(k, _, _)| k == key).unwrap().2;
+        let deepest_cluster = entries
+            .iter()
+            .filter(|(k, _, _)| k.starts_with(b"m/cluster/"))
+            .map(|&(_, _, d)| d)
+            .max()
+            .unwrap();
+        assert!(depth_of(b"z") < deepest_cluster);
     }
-}
 
-pub struct Iter<'a, V> {
-    tree: &'a HotTree<V>,
-    stack: Vec<Ptr>,
-}
+    #[test]
+    fn test_insert_with_prefix_hint() {
+        let key = |i: u32| format!("tenant-7/orders/{i:06}");
+        let detected = HotTree::<u32>::extract_natural_prefix(key(0).as_bytes()).len();
+        assert_eq!(detected, "tenant-7/".len());
+
+        let mut auto: HotTree<u32> = HotTree::new();
+        let mut hinted: HotTree<u32> = HotTree::new();
+        for i in 0..3000 {
+            auto.insert(key(i), i);
+            hinted.insert_with_prefix_hint(key(i), i, detected);
+        }
+        assert_eq!(hinted.memory_usage(), auto.memory_usage());
+        assert_eq!(hinted.structure_digest(), auto.structure_digest());
+        assert!(hinted.iter().eq(auto.iter()));
+
+        // A longer hint than any delimiter gives shares more bytes per key.
+        let mut longer: HotTree<u32> = HotTree::new();
+        for i in 0..3000 {
+            longer.insert_with_prefix_hint(key(i), i, "tenant-7/orders/".len());
+        }
+        assert!(longer.leaves.len() < auto.leaves.len());
+        assert!(longer.iter().eq(auto.iter()));
+        assert_eq!(longer.insert_with_prefix_hint(key(5), 50, 0), Some(5));
+        assert_eq!(longer.get(key(5)), Some(&50));
+    }
 
-impl<'a, V> Iterator for Iter<'a, V> {
-    type Item = (Vec<u8>, &'a V);
+    #[test]
+    fn test_insert_unique() {
+        let mut tree: HotTree<String> = HotTree::new();
+        assert_eq!(tree.insert_unique("req/1", "first".to_string()), Ok(()));
+        assert_eq!(
+            tree.insert_unique("req/1", "second".to_string()),
+            Err("second".to_string())
+        );
+        assert_eq!(tree.get("req/1").map(String::as_str), Some("first"));
+        assert_eq!(tree.len(), 1);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        while let Some(ptr) = self.stack.pop() {
-            if ptr.is_null() {
-                continue;
-            }
+        tree.remove("req/1");
+        assert_eq!(tree.insert_unique("req/1", "third".to_string()), Ok(()));
+        assert_eq!(tree.get("req/1").map(String::as_str), Some("third"));
+        assert_eq!(tree.len(), 1);
+    }
 
-            if ptr.is_leaf() {
-                let leaf_off = ptr.leaf_off();
-                if std::mem::size_of::<V>() == 0 {
-                    if ptr.is_tombstone() {
-                        continue;
-                    }
-                    let key = self.tree.get_leaf_key(leaf_off);
-                    return Some((key, self.tree.zst_value_ref()));
-                }
+    #[test]
+    fn test_compact_drops_unused_prefixes() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..2000 {
+            t.insert(format!("sessions.example.com/{i}"), i);
+        }
+        for i in 0..100 {
+            t.insert(format!("users.example.com/{i}"), i);
+            t.insert(format!("orders.example.com/{i}"), i);
+        }
+        let pool_len = t.prefix_pool.len();
+        for i in 0..2000 {
+            t.remove(format!("sessions.example.com/{i}"));
+        }
+        // Still referenced by removed keys' records until compaction.
+        assert_eq!(t.prefix_pool.len(), pool_len);
+        t.compact();
+        assert_eq!(
+            t.prefix_pool.len(),
+            pool_len - "sessions.example.com/".len()
+        );
+        assert_eq!(t.prefix_offsets.len(), 3);
 
-                let idx = self.tree.get_leaf_value_idx(leaf_off);
-                if let Some(ref value) = self.tree.values[idx] {
-                    let key = self.tree.get_leaf_key(leaf_off);
-                    return Some((key, value));
-                }
-                continue;
+        for i in 0..100 {
+            assert_eq!(t.get(format!("users.example.com/{i}")), Some(&i));
+            assert_eq!(t.get(format!("orders.example.com/{i}")), Some(&i));
+        }
+        assert_eq!(t.get("sessions.example.com/1"), None);
+        assert_eq!(t.iter_insertion_order().count(), 200);
+
+        // Dropped prefixes are learned again on demand.
+        t.insert("sessions.example.com/new", 7);
+        assert_eq!(t.get("sessions.example.com/new"), Some(&7));
+        assert_eq!(t.prefix_offsets.len(), 4);
+        assert_eq!(t.len(), 201);
+    }
+
+    #[test]
+    fn test_iter_changed_since() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..500 {
+            t.insert(format!("row/{i:03}"), i);
+        }
+        t.enable_change_tracking();
+        assert_eq!(t.iter_changed_since(0).count(), 500);
+
+        let gen = t.change_generation();
+        assert_eq!(t.iter_changed_since(gen).count(), 0);
+        t.insert("row/007", 70);
+        t.insert("row/new", 1);
+        *t.get_mut("row/300").unwrap() += 1;
+        t.remove("row/400");
+        let (_, handle) = t.insert_with_handle(b"row/handle", 2);
+        let after = t.change_generation();
+        *t.get_by_handle_mut(handle).unwrap() += 1;
+
+        let changed: Vec<Vec<u8>> = t.iter_changed_since(gen).map(|(k, _)| k).collect();
+        assert_eq!(
+            changed,
+            [&b"row/007"[..], b"row/300", b"row/handle", b"row/new"]
+        );
+        let changed: Vec<Vec<u8>> = t.iter_changed_since(after).map(|(k, _)| k).collect();
+        assert_eq!(changed, [b"row/handle".to_vec()]);
+
+        // Stamps follow their values when leaves are compacted.
+        t.compact_leaves();
+        assert_eq!(t.iter_changed_since(gen).count(), 4);
+        assert_eq!(t.get("row/007"), Some(&70));
+    }
+
+    #[test]
+    fn test_from_btreemap() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(2403);
+        let mut map: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+        for i in 0..5000u64 {
+            let len = rng.gen_range(1..40);
+            let key: Vec<u8> = (0..len).map(|_| rng.gen_range(b'a'..=b'f')).collect();
+            map.insert(key, i);
+        }
+        map.insert(b"https://example.com/".to_vec(), 1);
+        map.insert(b"https://example.com/a".to_vec(), 2);
+
+        let t: HotTree<u64> = HotTree::from_btreemap(&map);
+        assert_eq!(t.len(), map.len());
+        assert!(t.iter().eq(map.iter().map(|(k, v)| (k.clone(), v))));
+        for (k, v) in &map {
+            assert_eq!(t.get(k), Some(v));
+        }
+        for absent in [
+            &b""[..],
+            b"g",
+            b"https://example.com",
+            b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        ] {
+            assert_eq!(t.get(absent), map.get(absent));
+        }
+    }
+
+    #[test]
+    fn test_reserve_values_and_leaf_bytes() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.insert("seed", 0);
+        let n = 10_000;
+        t.reserve_values(n);
+        // "k" + 6 digits, with no prefix: 2 + 1 + 7 + 4 bytes per record.
+        t.reserve_leaf_bytes(n * 14);
+        let values_cap = t.values.capacity();
+        let leaves_cap = t.leaves.capacity();
+        assert!(values_cap > n);
+        for i in 0..n {
+            t.insert(format!("k{i:06}"), i as u64);
+            assert_eq!(t.values.capacity(), values_cap);
+            assert_eq!(t.leaves.capacity(), leaves_cap);
+        }
+        assert_eq!(t.len(), n + 1);
+    }
+
+    #[test]
+    fn test_get_each() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..1000 {
+            t.insert(format!("id:{i}"), i);
+        }
+        let keys: Vec<Vec<u8>> = [5, 2000, 999, 5, 0]
+            .iter()
+            .map(|i| format!("id:{i}").into_bytes())
+            .collect();
+        let mut out = Vec::new();
+        t.get_each(keys.iter().map(Vec::as_slice), |key, value| {
+            out.extend_from_slice(key);
+            out.push(b'=');
+            if let Some(v) = value {
+                out.extend_from_slice(&v.to_le_bytes());
             }
+            out.push(b';');
+        });
+        let mut expected = Vec::new();
+        for key in &keys {
+            expected.extend_from_slice(key);
+            expected.push(b'=');
+            if let Some(v) = t.get(key) {
+                expected.extend_from_slice(&v.to_le_bytes());
+            }
+            expected.push(b';');
+        }
+        assert_eq!(out, expected);
+    }
 
-            let node_off = ptr.node_off();
-            let n = self.tree.node_entry_count(node_off);
-            for i in (0..n).rev() {
-                self.stack.push(self.tree.node_entry_ptr(node_off, i));
+    #[test]
+    fn test_rebuild_prefixes() {
+        let key = |i: u32| format!("cache:v2/{:03}/{i}", i % 40);
+        // Stored under a stale scheme: hinted prefixes ending mid-segment, plus none at all.
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..4000 {
+            let hint = if i % 2 == 0 { "cache:v2/0".len() } else { 0 };
+            t.insert_with_prefix_hint(key(i), i, hint);
+        }
+        t.remove(key(3));
+        let mut fresh: HotTree<u32> = HotTree::new();
+        for i in (0..4000).filter(|&i| i != 3) {
+            fresh.insert(key(i), i);
+        }
+        assert_ne!(t.prefix_pool, fresh.prefix_pool);
+
+        t.rebuild_prefixes();
+        assert_eq!(t.prefix_pool, b"cache:");
+        assert_eq!(t.prefix_pool, fresh.prefix_pool);
+        assert_eq!(t.leaves.len(), fresh.leaves.len());
+        assert_eq!(t.dead_bytes(), 0);
+        assert_eq!(t.len(), 3999);
+        for i in 0..4000 {
+            assert_eq!(t.get(key(i)), (i != 3).then_some(&i));
+        }
+        assert!(t.iter().eq(fresh.iter()));
+    }
+
+    #[test]
+    fn test_prefix_groups() {
+        let mut t: HotTree<u32> = HotTree::new();
+        let mut keys = Vec::new();
+        for i in 0..300u32 {
+            keys.push(match i % 4 {
+                0 => format!("https://h{}/{i}", i % 3),
+                1 => format!("user:{i}"),
+                2 => format!("k{i}"),
+                _ => format!("/srv/data/{i}"),
+            });
+        }
+        for (i, key) in keys.iter().enumerate() {
+            t.insert(key, i as u32);
+        }
+
+        let mut seen = Vec::new();
+        let mut prefixes = Vec::new();
+        for (prefix, entries) in t.prefix_groups() {
+            prefixes.push(prefix.to_vec());
+            assert!(!entries.is_empty());
+            let mut prev: Option<Vec<u8>> = None;
+            for (suffix, value) in entries {
+                let key = [prefix.as_bytes(), &suffix].concat();
+                assert_eq!(t.get(&key), Some(value));
+                assert!(prev.as_ref().is_none_or(|p| *p < key));
+                prev = Some(key.clone());
+                seen.push(key);
             }
         }
-        None
+        assert!(prefixes.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(prefixes[0], b"");
+        assert!(prefixes.contains(&b"user:".to_vec()));
+        seen.sort();
+        assert!(seen.into_iter().eq(t.iter().map(|(k, _)| k)));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_min_key_len_for_prefix() {
+        let keys: Vec<String> = (0..2000)
+            .map(|i| {
+                if i % 2 == 0 {
+                    format!("abcd:{}", i % 10)
+                } else {
+                    format!("user:{i:05}")
+                }
+            })
+            .collect();
+        let mut scanned: HotTree<u32> = HotTree::new();
+        let mut skipped: HotTree<u32> = HotTree::new().with_min_key_len_for_prefix(9);
+        for (i, key) in keys.iter().enumerate() {
+            scanned.insert(key, i as u32);
+            skipped.insert(key, i as u32);
+        }
+        assert!(scanned.iter().eq(skipped.iter()));
+        for (key, value) in scanned.iter() {
+            assert_eq!(skipped.get(&key), Some(value));
+        }
+
+        let prefixes = |t: &HotTree<u32>| -> Vec<(Vec<u8>, u16)> {
+            t.debug_entries()
+                .into_iter()
+                .map(|e| (e.key, e.prefix_id))
+                .collect()
+        };
+        for (key, prefix_id) in prefixes(&skipped) {
+            assert_eq!(prefix_id != 0, key.len() >= 9, "{key:?}");
+        }
+        assert_eq!(scanned.prefix_pool, b"abcd:user:");
+        assert_eq!(skipped.prefix_pool, b"user:");
+    }
 
     #[test]
-    fn test_basic() {
-        let mut t: HotTree<u64> = HotTree::new();
-        t.insert(b"hello", 1);
-        t.insert(b"world", 2);
-        assert_eq!(t.get(b"hello"), Some(&1));
-        assert_eq!(t.get(b"world"), Some(&2));
-        assert_eq!(t.get(b"missing"), None);
-        assert_eq!(t.len(), 2);
+    fn test_range() {
+        let mut t: HotTree<u32> = HotTree::new();
+        let mut model = BTreeMap::new();
+        for i in 0..2000u32 {
+            let key = format!("k/{}", i * 7 % 1000).into_bytes();
+            t.insert(&key, i);
+            model.insert(key, i);
+        }
+        let lo = &b"k/3"[..];
+        let hi = &b"k/52"[..];
+        let pairs = |it: &mut dyn Iterator<Item = (Vec<u8>, &u32)>| -> Vec<(Vec<u8>, u32)> {
+            it.map(|(k, v)| (k, *v)).collect()
+        };
+        let expect = |r: (Bound<&[u8]>, Bound<&[u8]>)| -> Vec<(Vec<u8>, u32)> {
+            let r = (r.0.map(<[u8]>::to_vec), r.1.map(<[u8]>::to_vec));
+            model.range(r).map(|(k, v)| (k.clone(), *v)).collect()
+        };
+        for bounds in [
+            (Bound::Included(lo), Bound::Excluded(hi)),
+            (Bound::Excluded(lo), Bound::Included(hi)),
+            (Bound::Included(&b"k/30"[..]), Bound::Included(&b"k/30"[..])),
+            (Bound::Excluded(&b"k/30"[..]), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Excluded(lo)),
+        ] {
+            assert_eq!(pairs(&mut t.range(bounds)), expect(bounds));
+        }
+        assert_eq!(t.range(hi..lo).count(), 0);
+        assert_eq!(t.range(lo..).count(), model.range(lo.to_vec()..).count());
     }
 
     #[test]
-    fn test_update() {
+    fn test_compact_with_slack() {
         let mut t: HotTree<u64> = HotTree::new();
-        assert_eq!(t.insert(b"key", 1), None);
-        assert_eq!(t.insert(b"key", 2), Some(1));
-        assert_eq!(t.get(b"key"), Some(&2));
-        assert_eq!(t.len(), 1);
+        for i in 0..5000u64 {
+            t.insert(format!("item/{i}"), i);
+        }
+        for i in (0..5000u64).step_by(3) {
+            t.remove(format!("item/{i}"));
+        }
+        assert!(t.compact_with_slack(0.25) > 0);
+
+        let ratio = |cap: usize, len: usize| cap as f64 / len as f64;
+        assert!((ratio(t.leaves.capacity(), t.leaves.len()) - 1.25).abs() < 0.01);
+        assert!((ratio(t.nodes.data.capacity(), t.nodes.data.len()) - 1.25).abs() < 0.01);
+        assert!((ratio(t.values.capacity(), t.values.len()) - 1.25).abs() < 0.01);
+
+        let caps = |t: &HotTree<u64>| {
+            (
+                t.leaves.capacity(),
+                t.nodes.data.capacity(),
+                t.values.capacity(),
+            )
+        };
+        let before = caps(&t);
+        for i in 0..50u64 {
+            t.insert(format!("item/new{i}"), i);
+        }
+        assert_eq!(caps(&t), before);
+        assert_eq!(t.get("item/new7"), Some(&7));
+        assert_eq!(t.get("item/1"), Some(&1));
+        assert_eq!(t.get("item/3"), None);
     }
 
     #[test]
-    fn test_remove() {
-        let mut t: HotTree<u64> = HotTree::new();
+    fn test_walk_nodes() {
+        let mut t: HotTree<u32> = HotTree::new();
+        let mut visits = 0;
+        t.walk_nodes(|_| visits += 1);
+        assert_eq!(visits, 0);
+
+        for i in 0..3000u32 {
+            t.insert(format!("doc/{}", i.wrapping_mul(2654435761)), i);
+        }
+        let mut tags: BTreeMap<&'static str, usize> = BTreeMap::new();
+        let mut leaves = 0;
+        let mut first_height = None;
+        t.walk_nodes(|node| {
+            *tags.entry(node.tag).or_insert(0) += 1;
+            first_height.get_or_insert(node.height);
+            assert!(node.len() >= 2);
+            assert!(!node.discriminative_bits.is_empty());
+            assert!(node.discriminative_bits.windows(2).all(|w| w[0] < w[1]));
+            if node.height == 1 {
+                assert!(node.children.iter().all(|&c| c == ChildKind::Leaf));
+            }
+            leaves += node
+                .children
+                .iter()
+                .filter(|&&c| c == ChildKind::Leaf)
+                .count();
+        });
+        assert_eq!(tags, t.node_tag_histogram());
+        assert_eq!(leaves, t.len());
+        // Pre-order starts at the root.
+        assert_eq!(first_height, Some(t.height()));
+    }
+
+    #[test]
+    fn test_estimate_prefix_count() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for c in 0..8u32 {
+            for i in 0..(c + 1) * 3000 {
+                t.insert(format!("cat{c}/{}", i.wrapping_mul(2654435761)), i);
+            }
+        }
+        for c in 0..8u32 {
+            let prefix = format!("cat{c}/");
+            let exact = t.prefix_scan(prefix.as_bytes()).count();
+            assert_eq!(exact, (c as usize + 1) * 3000);
+            let estimate = t.estimate_prefix_count(prefix.as_bytes(), 512);
+            let error = (estimate as f64 - exact as f64).abs() / exact as f64;
+            assert!(
+                error < 0.25,
+                "{prefix}: estimated {estimate}, exact {exact}"
+            );
+            assert_eq!(t.estimate_prefix_count(prefix.as_bytes(), 512), estimate);
+        }
+        assert_eq!(t.estimate_prefix_count(b"dog", 64), 0);
+    }
+
+    #[test]
+    fn test_index_and_index_mut() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..1000u32 {
+            t.insert(format!("k{i}"), i);
+        }
+        assert_eq!(t[b"k7"], 7);
+        assert_eq!(t["k999"], 999);
+        assert_eq!(t[&b"k0"[..]], 0);
+
+        t[b"k7"] += 100;
+        t["k8"] = 0;
+        assert_eq!(t.get(b"k7"), Some(&107));
+        assert_eq!(t.get(b"k8"), Some(&0));
+        assert_eq!(t.len(), 1000);
+
+        let mut set: HotTree<()> = HotTree::new();
+        set.insert(b"present", ());
+        set[b"present"] = ();
+        assert_eq!(set[b"present"], ());
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn test_index_missing_key_panics() {
+        let mut t: HotTree<u32> = HotTree::new();
         t.insert(b"a", 1);
-        t.insert(b"b", 2);
-        t.insert(b"c", 3);
+        let _ = t[b"b"];
+    }
 
-        assert_eq!(t.remove(b"b"), Some(2));
-        assert_eq!(t.get(b"b"), None);
-        assert_eq!(t.len(), 2);
-        assert_eq!(t.get(b"a"), Some(&1));
-        assert_eq!(t.get(b"c"), Some(&3));
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn test_index_mut_missing_key_panics() {
+        let mut t: HotTree<u32> = HotTree::new();
+        t.insert(b"a", 1);
+        t[b"b"] = 2;
+    }
 
-        // Reinserting a removed key should increase length.
-        assert_eq!(t.insert(b"b", 4), None);
-        assert_eq!(t.get(b"b"), Some(&4));
-        assert_eq!(t.len(), 3);
+    #[test]
+    fn test_remove_many_matches_single_removes() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(2417);
+        let mut batched: HotTree<u32> = HotTree::new();
+        for i in 0..20_000u32 {
+            batched.insert(format!("user:{:05}:{}", rng.gen_range(0..50_000), i % 7), i);
+        }
+        let mut single = batched.clone();
+
+        let mut doomed: Vec<Vec<u8>> = batched
+            .iter()
+            .map(|(k, _)| k)
+            .filter(|_| rng.gen_bool(0.4))
+            .collect();
+        // Absent keys and duplicates are ignored.
+        doomed.push(b"user:99999:0".to_vec());
+        doomed.push(doomed[0].clone());
+        let batch: Vec<&[u8]> = doomed.iter().map(|k| k.as_slice()).collect();
+
+        let expected = batch.len() - 2;
+        let mut removed_single = 0;
+        for key in &batch {
+            removed_single += single.remove(key).is_some() as usize;
+        }
+        assert_eq!(removed_single, expected);
+        assert_eq!(batched.remove_many(&batch), expected);
+        assert_eq!(batched.len(), single.len());
+        assert!(batched.iter().eq(single.iter()));
+        for key in &doomed {
+            assert_eq!(batched.get(key), None);
+        }
+        for (key, value) in single.iter() {
+            assert_eq!(batched.get(&key), Some(value));
+        }
+        // Everything under one prefix empties whole subtrees at once.
+        let subtree: Vec<Vec<u8>> = batched.prefix_scan(b"user:1").map(|(k, _)| k).collect();
+        let subtree: Vec<&[u8]> = subtree.iter().map(|k| k.as_slice()).collect();
+        assert_eq!(batched.remove_many(&subtree), subtree.len());
+        assert!(!batched.contains_prefix(b"user:1"));
+        assert_eq!(batched.remove_many(&batch), 0);
+        assert_eq!(batched.remove_many(&[]), 0);
     }
 
     #[test]
-    fn test_zst_remove_and_reinsert() {
-        let mut t: HotTree<()> = HotTree::new();
-        assert_eq!(t.insert(b"a", ()), None);
-        assert_eq!(t.insert(b"b", ()), None);
-        assert_eq!(t.insert(b"c", ()), None);
-        assert_eq!(t.len(), 3);
-        assert_eq!(t.get(b"a"), Some(&()));
-        assert_eq!(t.get(b"b"), Some(&()));
-        assert_eq!(t.get(b"c"), Some(&()));
+    fn test_lookups_match_with_simd_forced_off() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
 
-        assert_eq!(t.remove(b"b"), Some(()));
-        assert_eq!(t.get(b"b"), None);
-        assert_eq!(t.get(b"a"), Some(&()));
-        assert_eq!(t.get(b"c"), Some(&()));
-        assert_eq!(t.len(), 2);
+        let mut rng = StdRng::seed_from_u64(2420);
+        let detected = SimdSupport::detect();
+        let scalar = SimdSupport {
+            bmi2: false,
+            avx2: false,
+        };
+        for _ in 0..10_000 {
+            let (value, mask) = (rng.gen::<u64>(), rng.gen::<u64>());
+            assert_eq!(
+                pext_u64(value, mask, detected),
+                pext_u64(value, mask, scalar)
+            );
+        }
 
-        // Reinsertion should clear the tombstone.
-        assert_eq!(t.insert(b"b", ()), None);
-        assert_eq!(t.get(b"b"), Some(&()));
-        assert_eq!(t.len(), 3);
+        let mut t: HotTree<u32> = HotTree::new();
+        assert_eq!(t.config.simd, detected);
+        let keys: Vec<Vec<u8>> = (0..20_000)
+            .map(|_| {
+                let len = rng.gen_range(1..24);
+                (0..len).map(|_| rng.gen_range(b'a'..=b'h')).collect()
+            })
+            .collect();
+        for (i, key) in keys.iter().enumerate() {
+            t.insert(key, i as u32);
+        }
+        let mut forced = t.clone();
+        forced.config.simd = scalar;
+        for key in &keys {
+            assert_eq!(t.get(key), forced.get(key));
+        }
+        for _ in 0..20_000 {
+            let probe: Vec<u8> = (0..rng.gen_range(1..24))
+                .map(|_| rng.gen_range(b'a'..=b'h'))
+                .collect();
+            assert_eq!(t.get(&probe), forced.get(&probe));
+        }
+    }
 
-        // Updating an existing key should return Some(()) and keep length stable.
-        assert_eq!(t.insert(b"b", ()), Some(()));
-        assert_eq!(t.len(), 3);
+    #[test]
+    fn test_keys_blob_round_trips() {
+        let empty: HotTree<u8> = HotTree::new();
+        assert_eq!(empty.keys_blob(), (Vec::new(), vec![0]));
+
+        let mut t: HotTree<u32> = HotTree::new().with_suffix_interning(true);
+        for i in 0..5000u32 {
+            t.insert(format!("tenant/{}/item:{}:active", i % 13, i), i);
+        }
+        t.insert(b"", 0);
+        t.remove(b"tenant/3/item:3:active");
+
+        let (blob, offsets) = t.keys_blob();
+        assert_eq!(offsets.len(), t.len() + 1);
+        assert_eq!(offsets[0], 0);
+        assert_eq!(*offsets.last().unwrap() as usize, blob.len());
+        let keys = offsets
+            .windows(2)
+            .map(|w| blob[w[0] as usize..w[1] as usize].to_vec());
+        assert!(keys.eq(t.iter().map(|(k, _)| k)));
     }
 
     #[test]
-    fn test_many() {
-        let mut t: HotTree<u64> = HotTree::new();
-        for i in 0..1000u64 {
-            let key = format!("key{:05}", i);
-            t.insert(key.as_bytes(), i);
+    fn test_content_checksum_ignores_layout() {
+        let mut t: HotTree<String> = HotTree::new();
+        for i in 0..3000 {
+            t.insert(format!("doc/{i:05}"), format!("body {i}"));
         }
-        assert_eq!(t.len(), 1000);
-        for i in 0..1000u64 {
-            let key = format!("key{:05}", i);
-            assert_eq!(t.get(key.as_bytes()), Some(&i), "Failed at {}", i);
+        for i in (0..3000).step_by(3) {
+            t.remove(format!("doc/{i:05}"));
+        }
+        let sum = t.content_checksum(|v| v.as_bytes());
+
+        // Same entries, different arena layout.
+        let mut rebuilt: HotTree<String> = HotTree::new();
+        for (k, v) in t.iter().collect::<Vec<_>>().into_iter().rev() {
+            rebuilt.insert(k, v.clone());
         }
+        assert_eq!(rebuilt.content_checksum(|v| v.as_bytes()), sum);
+        t.compact();
+        assert_eq!(t.content_checksum(|v| v.as_bytes()), sum);
+
+        t.get_mut(b"doc/00001").unwrap().push('!');
+        assert_ne!(t.content_checksum(|v| v.as_bytes()), sum);
+        // Moving a byte between key and value changes it too.
+        let mut a: HotTree<String> = HotTree::new();
+        a.insert(b"ab", "c".to_string());
+        let mut b: HotTree<String> = HotTree::new();
+        b.insert(b"a", "bc".to_string());
+        assert_ne!(
+            a.content_checksum(|v| v.as_bytes()),
+            b.content_checksum(|v| v.as_bytes())
+        );
     }
 
     #[test]
-    fn test_prefix_compression() {
+    fn test_try_get_reports_corruption() {
         let mut t: HotTree<u64> = HotTree::new();
-        // URLs with shared prefix
-        t.insert(b"https://example.com/page1", 1);
-        t.insert(b"https://example.com/page2", 2);
-        t.insert(b"https://example.com/page3", 3);
-        t.insert(b"https://other.com/page1", 4);
+        for i in 0..5000u64 {
+            t.insert(format!("item:{i}"), i);
+        }
+        for i in 0..5000u64 {
+            assert_eq!(t.try_get(format!("item:{i}")), Ok(Some(&i)));
+        }
+        assert_eq!(t.try_get(b"item:x"), Ok(None));
 
-        assert_eq!(t.get(b"https://example.com/page1"), Some(&1));
-        assert_eq!(t.get(b"https://example.com/page2"), Some(&2));
-        assert_eq!(t.get(b"https://example.com/page3"), Some(&3));
-        assert_eq!(t.get(b"https://other.com/page1"), Some(&4));
+        let corrupt_root = |bad: Ptr| {
+            let mut c = t.clone();
+            let root = c.root.node_off();
+            for i in 0..c.node_entry_count(root) {
+                c.node_set_entry_ptr(root, i, bad);
+            }
+            c
+        };
+        let past_nodes = t.nodes.data.len() as u64 + 64;
+        assert_eq!(
+            corrupt_root(Ptr::node(past_nodes)).try_get(b"item:7"),
+            Err(Corruption::Node { off: past_nodes })
+        );
+        let past_leaves = t.leaves.len() as u64 - 1;
+        assert_eq!(
+            corrupt_root(Ptr::leaf(past_leaves)).try_get(b"item:7"),
+            Err(Corruption::Leaf { off: past_leaves })
+        );
+        // A node pointing at itself never reaches a leaf.
+        let root = t.root;
+        assert_eq!(
+            corrupt_root(root).try_get(b"item:7"),
+            Err(Corruption::Descent)
+        );
 
-        // Check that prefixes were learned
-        assert!(t.prefix_offsets.len() > 1, "Should have learned prefixes");
+        let mut bad_value = t.clone();
+        bad_value.values.truncate(10);
+        assert!(matches!(
+            bad_value.try_get(b"item:4000"),
+            Err(Corruption::ValueIndex { .. })
+        ));
+        assert_eq!(bad_value.try_get(b"item:3"), Ok(Some(&3)));
     }
 
     #[test]
-    fn test_iter() {
+    fn test_iter_with_ghosts() {
+        let mut t: HotTree<u32> = HotTree::new();
+        for i in 0..100u32 {
+            t.insert(format!("k{i:03}"), i);
+        }
+        t.remove(b"k010");
+        assert!(t.iter_with_ghosts().eq(t.iter().map(|(k, v)| (k, Some(v)))));
+
+        // Emptying a slot behind the tree's back leaves its leaf linked.
+        let slot = t.values_mut().iter().position(|v| *v == Some(42)).unwrap();
+        t.values_mut()[slot] = None;
+        let ghosts: Vec<Vec<u8>> = t
+            .iter_with_ghosts()
+            .filter(|(_, v)| v.is_none())
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(ghosts, [b"k042".to_vec()]);
+        assert_eq!(t.iter_with_ghosts().count(), 99);
+        assert_eq!(t.iter().count(), 98);
+        assert!(t.iter().all(|(k, _)| k != b"k042"));
+    }
+
+    #[test]
+    fn test_get_mut() {
         let mut t: HotTree<u64> = HotTree::new();
-        t.insert(b"b", 2);
-        t.insert(b"a", 1);
-        t.insert(b"c", 3);
+        t.insert(b"counter", 0);
+        t.insert(b"other", 5);
+        let leaves = t.leaves.len();
+        let values = t.values.len();
 
-        let pairs: Vec<_> = t.iter().collect();
-        assert_eq!(pairs.len(), 3);
-        assert_eq!(pairs[0], (b"a".to_vec(), &1));
-        assert_eq!(pairs[1], (b"b".to_vec(), &2));
-        assert_eq!(pairs[2], (b"c".to_vec(), &3));
+        for _ in 0..1000 {
+            *t.get_mut(b"counter").unwrap() += 1;
+        }
+        assert_eq!(t.get(b"counter"), Some(&1000));
+        assert_eq!(t.get(b"other"), Some(&5));
+        assert!(t.get_mut(b"missing").is_none());
+        assert_eq!(t.leaves.len(), leaves);
+        assert_eq!(t.values.len(), values);
+
+        let mut z: HotTree<()> = HotTree::new();
+        z.insert(b"k", ());
+        assert!(z.get_mut(b"k").is_some());
+        assert!(z.get_mut(b"x").is_none());
+    }
+
+    #[test]
+    fn test_get_prepared() {
+        let mut t: HotTree<u64> = HotTree::new();
+        t.insert(b"alpha", 1);
+        t.insert(b"beta", 2);
+
+        let alpha = t.prepare(b"alpha");
+        let gamma = t.prepare(b"gamma");
+        assert_eq!(t.get_prepared(&alpha), Some(&1));
+        assert_eq!(t.get_prepared(&gamma), None);
+
+        // Updating a value keeps the prepared key current.
+        t.insert(b"alpha", 10);
+        assert_eq!(t.get_prepared(&alpha), Some(&10));
+
+        // Structural changes invalidate it; lookups fall back to a descent.
+        t.insert(b"gamma", 3);
+        assert_eq!(t.get_prepared(&gamma), Some(&3));
+        t.remove(b"alpha");
+        assert_eq!(t.get_prepared(&alpha), None);
+        t.insert(b"alpha", 4);
+        assert_eq!(t.get_prepared(&alpha), Some(&4));
+        assert_eq!(alpha.key(), b"alpha");
+
+        // Another tree, or a clone, with the same generation looks the key up itself.
+        let mut t1: HotTree<u64> = HotTree::new();
+        let mut t2: HotTree<u64> = HotTree::new();
+        t1.insert(b"alpha", 1);
+        t2.insert(b"beta", 2);
+        assert_eq!(t1.generation, t2.generation);
+        let alpha = t1.prepare(b"alpha");
+        assert_eq!(t2.get_prepared(&alpha), None);
+        let mut clone = t1.clone();
+        clone.remove(b"alpha");
+        t1.insert(b"gamma", 3);
+        assert_eq!(t1.generation, clone.generation);
+        let alpha = t1.prepare(b"alpha");
+        assert_eq!(t1.get_prepared(&alpha), Some(&1));
+        assert_eq!(clone.get_prepared(&alpha), None);
     }
 
     #[test]
@@ -3222,5 +8023,8 @@ mod tests {
     }
 }
 
+#[cfg(feature = "rayon")]
+mod par;
+
 #[cfg(test)]
 mod proptests;
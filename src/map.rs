@@ -0,0 +1,250 @@
+//! A common interface over byte-keyed maps, and adaptors built on it.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::hash::BuildHasher;
+use std::io::{self, BufRead};
+
+use crate::HotTree;
+
+/// An ordered map from byte-string keys to `V`.
+pub trait ByteMap<V> {
+    fn insert(&mut self, key: &[u8], value: V) -> Option<V>;
+    fn get(&self, key: &[u8]) -> Option<&V>;
+    fn remove(&mut self, key: &[u8]) -> Option<V>;
+    fn len(&self) -> usize;
+
+    /// The concrete map, for downcasting a `dyn ByteMap` to reach type-specific methods:
+    /// [`HotTree<V, S>`], `BTreeMap<Vec<u8>, V>` or [`TransformedKV<M, F>`].
+    fn as_any(&self) -> &dyn Any
+    where
+        Self: 'static;
+
+    fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<V, S: BuildHasher> ByteMap<V> for HotTree<V, S> {
+    fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        HotTree::insert(self, key, value)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&V> {
+        HotTree::get(self, key)
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        HotTree::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        HotTree::len(self)
+    }
+
+    fn as_any(&self) -> &dyn Any
+    where
+        Self: 'static,
+    {
+        self
+    }
+}
+
+impl<V> ByteMap<V> for BTreeMap<Vec<u8>, V> {
+    fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        BTreeMap::insert(self, key.to_vec(), value)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&V> {
+        BTreeMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        BTreeMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn as_any(&self) -> &dyn Any
+    where
+        Self: 'static,
+    {
+        self
+    }
+}
+
+/// A [`ByteMap`] that normalizes every key with `transform` before delegating to `M`.
+///
+/// Only normalized keys are stored, so iterating the inner map (via
+/// [`TransformedKV::inner`]) yields normalized keys, not the originals.
+///
+/// ```
+/// use hot_rs::{ByteMap, HotTree, TransformedKV};
+///
+/// let mut m = TransformedKV::new(HotTree::<u32>::new(), |k: &[u8]| k.to_ascii_lowercase());
+/// m.insert(b"USER", 1);
+/// assert_eq!(m.get(b"user"), Some(&1));
+/// ```
+pub struct TransformedKV<M, F> {
+    inner: M,
+    transform: F,
+}
+
+impl<M, F: Fn(&[u8]) -> Vec<u8>> TransformedKV<M, F> {
+    pub fn new(inner: M, transform: F) -> Self {
+        Self { inner, transform }
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> M {
+        self.inner
+    }
+}
+
+impl<V, M: ByteMap<V>, F: Fn(&[u8]) -> Vec<u8>> ByteMap<V> for TransformedKV<M, F> {
+    fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        self.inner.insert(&(self.transform)(key), value)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&V> {
+        self.inner.get(&(self.transform)(key))
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        self.inner.remove(&(self.transform)(key))
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn as_any(&self) -> &dyn Any
+    where
+        Self: 'static,
+    {
+        self
+    }
+}
+
+/// Insert the `key\tvalue` lines of `reader` into `map`, returning how many lines were
+/// inserted.
+///
+/// The value follows the last tab and is a decimal `u64`; the key is every byte before it,
+/// so keys need not be UTF-8. Blank lines are skipped and a trailing `\r` is ignored. A line
+/// without a tab or with an invalid value stops the load with an
+/// [`io::ErrorKind::InvalidData`] error naming the line; lines before it stay inserted.
+pub fn load_tsv<M: ByteMap<u64> + ?Sized, R: BufRead>(
+    map: &mut M,
+    mut reader: R,
+) -> io::Result<usize> {
+    let mut line = Vec::new();
+    let mut line_no = 0;
+    let mut inserted = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(inserted);
+        }
+        line_no += 1;
+        let trimmed = line.strip_suffix(b"\n").unwrap_or(&line);
+        let trimmed = trimmed.strip_suffix(b"\r").unwrap_or(trimmed);
+        if trimmed.is_empty() {
+            continue;
+        }
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let tab = trimmed
+            .iter()
+            .rposition(|&b| b == b'\t')
+            .ok_or_else(|| invalid(format!("line {line_no}: expected key<TAB>value")))?;
+        let value = std::str::from_utf8(&trimmed[tab + 1..])
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "line {line_no}: invalid u64 value {:?}",
+                    String::from_utf8_lossy(&trimmed[tab + 1..])
+                ))
+            })?;
+        map.insert(&trimmed[..tab], value);
+        inserted += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transformed_case_insensitive() {
+        let mut m = TransformedKV::new(HotTree::<u64>::new(), |k: &[u8]| k.to_ascii_lowercase());
+        assert_eq!(m.insert(b"USER", 1), None);
+        assert_eq!(m.insert(b"user", 2), Some(1));
+        assert_eq!(m.get(b"User"), Some(&2));
+        assert!(m.contains_key(b"uSeR"));
+        assert_eq!(m.len(), 1);
+
+        let keys: Vec<Vec<u8>> = m.inner().iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![b"user".to_vec()]);
+
+        assert_eq!(m.remove(b"USER"), Some(2));
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn test_downcast_byte_map() {
+        let mut maps: Vec<Box<dyn ByteMap<u64>>> =
+            vec![Box::new(HotTree::<u64>::new()), Box::new(BTreeMap::new())];
+        for map in &mut maps {
+            map.insert(b"b", 2);
+            map.insert(b"a", 1);
+        }
+
+        let tree = maps[0].as_any().downcast_ref::<HotTree<u64>>().unwrap();
+        assert_eq!(tree.first_with_prefix(b""), Some((b"a".to_vec(), &1)));
+        assert!(maps[0]
+            .as_any()
+            .downcast_ref::<BTreeMap<Vec<u8>, u64>>()
+            .is_none());
+        let btree = maps[1]
+            .as_any()
+            .downcast_ref::<BTreeMap<Vec<u8>, u64>>()
+            .unwrap();
+        assert_eq!(btree.last_key_value(), Some((&b"b".to_vec(), &2)));
+    }
+
+    #[test]
+    fn test_load_tsv() {
+        let tsv = b"alpha\t1\nbeta\t22\r\n\nkey\twith tab\t333\n\nalpha\t4";
+        let mut tree = HotTree::<u64>::new();
+        assert_eq!(load_tsv(&mut tree, &tsv[..]).unwrap(), 4);
+        let entries: Vec<(Vec<u8>, u64)> = tree.iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(
+            entries,
+            [
+                (b"alpha".to_vec(), 4),
+                (b"beta".to_vec(), 22),
+                (b"key\twith tab".to_vec(), 333),
+            ]
+        );
+
+        let mut model = BTreeMap::new();
+        let err = load_tsv(&mut model, &b"a\t1\nb\tx\nc\t3\n"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 2"), "{err}");
+        assert_eq!(model.len(), 1);
+        let err = load_tsv(&mut model, &b"no tab here\n"[..]).unwrap_err();
+        assert!(
+            err.to_string().contains("line 1: expected key<TAB>value"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Lockstep iteration over two trees in key order.
+
+use std::cmp::Ordering;
+use std::hash::BuildHasher;
+use std::iter::Peekable;
+
+use crate::{HotTree, Iter, SortedIterator};
+
+/// A key present in the left tree, the right tree, or both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EitherOrBoth<A, B> {
+    Both(A, B),
+    Left(A),
+    Right(B),
+}
+
+/// Iterator returned by [`merge_join`].
+pub struct MergeJoin<'a, V, S: BuildHasher = crate::FnvBuildHasher> {
+    a: Peekable<Iter<'a, V, S>>,
+    b: Peekable<Iter<'a, V, S>>,
+}
+
+/// Walk `a` and `b` together in ascending key order, pairing values of keys present in
+/// both and yielding the rest as singletons. Neither tree is materialized.
+pub fn merge_join<'a, V, S: BuildHasher>(
+    a: &'a HotTree<V, S>,
+    b: &'a HotTree<V, S>,
+) -> MergeJoin<'a, V, S> {
+    MergeJoin {
+        a: a.iter().peekable(),
+        b: b.iter().peekable(),
+    }
+}
+
+impl<'a, V, S: BuildHasher> Iterator for MergeJoin<'a, V, S> {
+    type Item = (Vec<u8>, EitherOrBoth<&'a V, &'a V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((ka, _)), Some((kb, _))) => ka.cmp(kb),
+        };
+        match order {
+            Ordering::Less => {
+                let (k, v) = self.a.next()?;
+                Some((k, EitherOrBoth::Left(v)))
+            }
+            Ordering::Greater => {
+                let (k, v) = self.b.next()?;
+                Some((k, EitherOrBoth::Right(v)))
+            }
+            Ordering::Equal => {
+                let (k, va) = self.a.next()?;
+                let (_, vb) = self.b.next()?;
+                Some((k, EitherOrBoth::Both(va, vb)))
+            }
+        }
+    }
+}
+
+impl<V, S: BuildHasher> SortedIterator for MergeJoin<'_, V, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_merge_join_intersection() {
+        let mut a: HotTree<u64> = HotTree::new();
+        let mut b: HotTree<u64> = HotTree::new();
+        let mut ma = BTreeMap::new();
+        let mut mb = BTreeMap::new();
+        for i in 0..3000u64 {
+            let key = format!("https://example.com/{i}").into_bytes();
+            if i % 2 == 0 {
+                a.insert(&key, i);
+                ma.insert(key.clone(), i);
+            }
+            if i % 3 == 0 {
+                b.insert(&key, i * 10);
+                mb.insert(key, i * 10);
+            }
+        }
+
+        let got: Vec<(Vec<u8>, u64, u64)> = merge_join(&a, &b)
+            .filter_map(|(k, e)| match e {
+                EitherOrBoth::Both(x, y) => Some((k, *x, *y)),
+                _ => None,
+            })
+            .collect();
+        let expected: Vec<(Vec<u8>, u64, u64)> = ma
+            .iter()
+            .filter_map(|(k, x)| Some((k.clone(), *x, *mb.get(k)?)))
+            .collect();
+        assert_eq!(got, expected);
+
+        let all: Vec<Vec<u8>> = merge_join(&a, &b).map(|(k, _)| k).collect();
+        let mut union: Vec<Vec<u8>> = ma.keys().chain(mb.keys()).cloned().collect();
+        union.sort();
+        union.dedup();
+        assert_eq!(all, union);
+    }
+}
@@ -0,0 +1,269 @@
+//! Parallel bulk construction (`rayon` feature).
+
+use super::*;
+
+use rayon::prelude::*;
+
+/// Inputs smaller than this are split no further.
+const MIN_CHUNK: usize = 4096;
+/// Bound on how many times a run is split in two on its way to becoming a chunk.
+const MAX_SPLIT_DEPTH: u32 = 12;
+
+/// How a sorted run was partitioned: each `Split` is a PATRICIA branch on `disc`, so every
+/// chunk owns a disjoint bit-prefix region of the key space.
+enum Plan {
+    Chunk(usize),
+    Split {
+        disc: u16,
+        left: Box<Plan>,
+        right: Box<Plan>,
+    },
+}
+
+impl Plan {
+    fn chunks(&self) -> usize {
+        match self {
+            Plan::Chunk(_) => 1,
+            Plan::Split { left, right, .. } => left.chunks() + right.chunks(),
+        }
+    }
+}
+
+#[inline]
+fn bit_at(key: &[u8], pos: u16) -> u8 {
+    (key_byte(key, (pos / 8) as usize) >> (7 - pos % 8)) & 1
+}
+
+fn first_diff_bit(a: &[u8], b: &[u8]) -> u16 {
+    first_diff_bit_by(a.len(), b.len(), |i| key_byte(a, i), |i| key_byte(b, i))
+        .expect("strictly ascending keys differ in some bit")
+}
+
+fn plan<K: AsRef<[u8]>, V>(
+    entries: &[(K, V)],
+    lo: usize,
+    hi: usize,
+    depth: u32,
+    target: usize,
+    bounds: &mut Vec<usize>,
+) -> Plan {
+    if hi - lo <= target || depth == MAX_SPLIT_DEPTH {
+        bounds.push(hi);
+        return Plan::Chunk(bounds.len() - 1);
+    }
+
+    let disc = first_diff_bit(entries[lo].0.as_ref(), entries[hi - 1].0.as_ref());
+    // Sorted keys sharing every bit before `disc` are ordered by it, so the run splits in two.
+    let mid = lo + entries[lo..hi].partition_point(|(k, _)| bit_at(k.as_ref(), disc) == 0);
+    debug_assert!(lo < mid && mid < hi);
+
+    Plan::Split {
+        disc,
+        left: Box::new(plan(entries, lo, mid, depth + 1, target, bounds)),
+        right: Box::new(plan(entries, mid, hi, depth + 1, target, bounds)),
+    }
+}
+
+impl<V: Send, S: BuildHasher + Default + Send> HotTree<V, S> {
+    /// Parallel [`HotTree::build_sorted`].
+    ///
+    /// The input is partitioned at discriminative bits so that each chunk is a complete
+    /// subtree of the final trie; chunks are built on the rayon pool, and the splits above
+    /// them are packed into compound nodes of up to 32 entries. The result holds the same
+    /// entries as the sequential build, though its node layout may differ.
+    ///
+    /// Grafting the chunks into one tree runs on the calling thread and rebuilds and
+    /// re-stores every key so that all chunks share one prefix table. That pass costs
+    /// about a tenth as much as a sequential build, which bounds the speedup.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not strictly ascending.
+    pub fn par_build_sorted<K: AsRef<[u8]> + Send>(
+        mut entries: Vec<(K, V)>,
+    ) -> Result<Self, HotError> {
+        let n = entries.len();
+        let target = n.div_ceil(rayon::current_num_threads() * 4).max(MIN_CHUNK);
+        if n <= target {
+            return Self::build_sorted(entries);
+        }
+
+        for (i, (key, _)) in entries.iter().enumerate() {
+            let key = key.as_ref();
+            if key.len() > MAX_KEY_LEN {
+                return Err(HotError::KeyTooLong {
+                    len: key.len(),
+                    max: MAX_KEY_LEN,
+                });
+            }
+            if i > 0 {
+                assert!(
+                    entries[i - 1].0.as_ref() < key,
+                    "par_build_sorted: keys must be strictly ascending"
+                );
+            }
+        }
+
+        let mut bounds = Vec::new();
+        let plan = plan(&entries, 0, n, 0, target, &mut bounds);
+
+        let mut chunks = Vec::with_capacity(bounds.len());
+        for &at in bounds[..bounds.len() - 1].iter().rev() {
+            chunks.push(entries.split_off(at));
+        }
+        chunks.push(entries);
+        chunks.reverse();
+
+        let subtrees: Vec<Self> = chunks
+            .into_par_iter()
+            .map(Self::build_sorted)
+            .collect::<Result<_, _>>()?;
+
+        // Graft in key order so the prefix table learns prefixes as a sequential build would.
+        let mut tree = Self::default();
+        let roots: Vec<Ptr> = subtrees.into_iter().map(|t| tree.graft(t)).collect();
+        tree.root = tree.stitch(&plan, &roots)?;
+        Ok(tree)
+    }
+
+    /// Move `other`'s leaves and values into `self` and copy its nodes into `self.nodes`,
+    /// returning the (unlinked) root of the copied subtree. Each key is rebuilt and stored
+    /// again so that it is compressed against `self`'s prefix table.
+    fn graft(&mut self, mut other: Self) -> Ptr {
+        let mut values = std::mem::take(&mut other.values);
+        let mut zst_values = std::mem::take(&mut other.zst_values);
+        self.count += other.count;
+
+        let mut nodes = std::mem::replace(&mut self.nodes, NodeArena::new(self.config.growth));
+        let mut key = Vec::new();
+        let (root, _) = Self::copy_subtree(&other.nodes, other.root, &mut nodes, |leaf| {
+            key.clear();
+            other.leaf_key(leaf.leaf_off()).extend_into(&mut key);
+
+            let new_leaf = self.store_leaf(&key, None);
+            if std::mem::size_of::<V>() == 0 {
+                let value = zst_values
+                    .pop()
+                    .expect("ZST values must track live key count");
+                self.zst_values.push(value);
+            } else {
+                let idx = other.get_leaf_value_idx(leaf.leaf_off());
+                self.values.push(values[idx].take());
+            }
+            new_leaf
+        });
+        self.nodes = nodes;
+        root
+    }
+
+    /// Link the chunk `roots` under compound nodes following `plan`'s splits.
+    ///
+    /// Each node is grown top-down from a split by repeatedly expanding the frontier split
+    /// that covers the most chunks, until the node has `MAX_COMPOUND_ENTRIES` entries or no
+    /// split is left whose bit the node's mapping can still represent.
+    fn stitch(&mut self, plan: &Plan, roots: &[Ptr]) -> Result<Ptr, HotError> {
+        if let Plan::Chunk(i) = plan {
+            return Ok(roots[*i]);
+        }
+
+        // Each entry is a frontier plan and the discriminative bits on which its path goes right.
+        let mut entries: Vec<(&Plan, Vec<u16>)> = vec![(plan, Vec::new())];
+        let mut disc_bits: Vec<u16> = Vec::new();
+        while entries.len() < MAX_COMPOUND_ENTRIES {
+            let mut best: Option<(usize, usize)> = None;
+            for (i, (entry, _)) in entries.iter().enumerate() {
+                let Plan::Split { disc, .. } = entry else {
+                    continue;
+                };
+                let chunks = entry.chunks();
+                if best.is_some_and(|(_, most)| most >= chunks) {
+                    continue;
+                }
+                let mut bits = disc_bits.clone();
+                bits.push(*disc);
+                bits.sort_unstable();
+                bits.dedup();
+                if DiscriminativeBitsRepresentation::try_build_minimal(&bits).is_some() {
+                    best = Some((i, chunks));
+                }
+            }
+            let Some((i, _)) = best else {
+                break;
+            };
+
+            let (entry, path) = entries[i].clone();
+            let Plan::Split { disc, left, right } = entry else {
+                unreachable!("only splits are expanded");
+            };
+            let mut right_path = path.clone();
+            right_path.push(*disc);
+            entries.splice(i..=i, [(&**left, path), (&**right, right_path)]);
+            disc_bits.push(*disc);
+            disc_bits.sort_unstable();
+            disc_bits.dedup();
+        }
+
+        let mapping = DiscriminativeBitsRepresentation::build_minimal(&disc_bits);
+        let k = disc_bits.len();
+        let mut sparse = [0u32; MAX_COMPOUND_ENTRIES];
+        let mut ptrs = [Ptr::NULL; MAX_COMPOUND_ENTRIES];
+        for (i, (entry, path)) in entries.iter().enumerate() {
+            ptrs[i] = self.stitch(entry, roots)?;
+            if self.ptr_height(ptrs[i]) >= self.config.max_height {
+                return Err(HotError::HeightLimit {
+                    max: self.config.max_height,
+                });
+            }
+            // The most significant discriminative bit is the highest dense bit.
+            for bit in path {
+                let idx = disc_bits
+                    .binary_search(bit)
+                    .expect("path bits are node bits");
+                sparse[i] |= 1 << (k - 1 - idx);
+            }
+        }
+        Ok(self.build_subtree_from_range(mapping, &sparse, &ptrs, 0, entries.len()))
+    }
+}
+
+impl<V: Clone + Send + Sync, S: BuildHasher + Sync> HotTree<V, S> {
+    /// Parallel [`HotTree::prefix_scan`], collecting cloned values.
+    ///
+    /// Seeks to `prefix` as the sequential scan does, keeps the pending subtrees that can
+    /// hold matching keys, and splits them into their children until there is work for
+    /// every thread. Each subtree reconstructs its keys on the rayon pool; the subtrees are
+    /// disjoint and ordered, so concatenating their results preserves key order.
+    pub fn par_prefix_scan(&self, prefix: &[u8]) -> Vec<(Vec<u8>, V)> {
+        let target = rayon::current_num_threads() * 4;
+        let mut tasks = self.prefix_subtrees(prefix);
+        while tasks.len() < target && tasks.iter().any(|ptr| !ptr.is_leaf()) {
+            let mut split = Vec::with_capacity(tasks.len() * 2);
+            for ptr in tasks {
+                if ptr.is_leaf() {
+                    split.push(ptr);
+                } else {
+                    let node_off = ptr.node_off();
+                    let n = self.node_entry_count(node_off);
+                    split.extend((0..n).map(|i| self.node_entry_ptr(node_off, i)));
+                }
+            }
+            tasks = split;
+        }
+
+        let parts: Vec<Vec<(Vec<u8>, V)>> = tasks
+            .into_par_iter()
+            .map(|ptr| {
+                let mut walk = LeafWalk {
+                    tree: self,
+                    stack: vec![ptr],
+                };
+                std::iter::from_fn(|| walk.next_leaf())
+                    .map(|(leaf_off, value)| (self.get_leaf_key(leaf_off), value))
+                    .take_while(|(key, _)| key.starts_with(prefix))
+                    .map(|(key, value)| (key, value.clone()))
+                    .collect()
+            })
+            .collect();
+        parts.into_iter().flatten().collect()
+    }
+}
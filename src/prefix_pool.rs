@@ -0,0 +1,94 @@
+//! Prefix tables shared by several trees.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::{FnvBuildHasher, HotTree, MAX_PREFIXES};
+
+/// A frozen set of key prefixes that several trees can compress against, so prefixes
+/// common to all of them are stored once instead of once per tree.
+///
+/// The pool is immutable once built and is shared through an `Arc`, so trees on different
+/// threads can use it without locking. Prefixes a tree meets that are not in the pool are
+/// learned into that tree's own table as usual. [`HotTree::memory_usage`] does not include
+/// the pool; see [`SharedPrefixPool::memory_usage`].
+///
+/// ```
+/// use std::sync::Arc;
+/// use hot_rs::{HotTree, SharedPrefixPool};
+///
+/// let pool = Arc::new(SharedPrefixPool::from_keys(["https://example.com/a"]));
+/// let mut tenant: HotTree<u32> = HotTree::new_with_pool(pool);
+/// tenant.insert("https://example.com/b", 1);
+/// assert_eq!(tenant.get("https://example.com/b"), Some(&1));
+/// ```
+#[derive(Debug, Default)]
+pub struct SharedPrefixPool {
+    pool: Vec<u8>,
+    /// Offset of each prefix in `pool`; prefix ids are `1..=offsets.len()` (0 is empty).
+    offsets: Vec<u32>,
+    /// FNV hash of each prefix to its id; prefixes whose hash collides are left out.
+    ids: HashMap<u64, u16>,
+}
+
+impl SharedPrefixPool {
+    /// Learn the natural prefix (up to the first `/`, `:` or `\`) of each key, as a tree
+    /// would while inserting them.
+    pub fn from_keys<K: AsRef<[u8]>>(keys: impl IntoIterator<Item = K>) -> Self {
+        let mut pool = Self::default();
+        for key in keys {
+            let prefix = HotTree::<()>::extract_natural_prefix(key.as_ref());
+            if prefix.is_empty() || pool.len() + 1 >= MAX_PREFIXES {
+                continue;
+            }
+            let hash = Self::hash(prefix);
+            if !pool.ids.contains_key(&hash) {
+                pool.offsets.push(pool.pool.len() as u32);
+                pool.pool.extend_from_slice(prefix);
+                pool.ids.insert(hash, pool.offsets.len() as u16);
+            }
+        }
+        pool.pool.shrink_to_fit();
+        pool.offsets.shrink_to_fit();
+        pool.ids.shrink_to_fit();
+        pool
+    }
+
+    /// Number of prefixes in the pool.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.pool.capacity() + self.offsets.capacity() * 4 + self.ids.capacity() * 16
+    }
+
+    #[inline]
+    fn hash(prefix: &[u8]) -> u64 {
+        let mut hasher = FnvBuildHasher::default().build_hasher();
+        hasher.write(prefix);
+        hasher.finish()
+    }
+
+    #[inline]
+    pub(crate) fn id(&self, prefix: &[u8]) -> Option<u16> {
+        let id = *self.ids.get(&Self::hash(prefix))?;
+        (self.get(id) == prefix).then_some(id)
+    }
+
+    /// Bytes of prefix `id`, for `1 <= id <= len()`.
+    #[inline]
+    pub(crate) fn get(&self, id: u16) -> &[u8] {
+        let idx = id as usize - 1;
+        let start = self.offsets[idx] as usize;
+        let end = self
+            .offsets
+            .get(idx + 1)
+            .map_or(self.pool.len(), |&o| o as usize);
+        &self.pool[start..end]
+    }
+}
@@ -18,6 +18,7 @@ fn validate_tree<V>(t: &HotTree<V>) {
     }
 
     let mut leaf_count = 0usize;
+    let mut prefix_refs = vec![0u32; t.prefix_refs.len()];
     while let Some(ptr) = stack.pop() {
         assert!(!ptr.is_null(), "NULL pointer inside tree");
 
@@ -25,6 +26,12 @@ fn validate_tree<V>(t: &HotTree<V>) {
             assert!(!ptr.is_tombstone(), "tombstone leaf should be unreachable");
 
             leaf_count += 1;
+            let rec = t.leaf_record(ptr.leaf_off());
+            for id in [rec.prefix_id, rec.tail_id] {
+                if id > 0 {
+                    prefix_refs[id as usize] += 1;
+                }
+            }
 
             if std::mem::size_of::<V>() != 0 {
                 let idx = t.get_leaf_value_idx(ptr.leaf_off());
@@ -56,7 +63,7 @@ fn validate_tree<V>(t: &HotTree<V>) {
         }
         assert_eq!(
             t.nodes.height(node_off),
-            max_child_h.saturating_add(1),
+            max_child_h + 1,
             "stored node height must match children"
         );
 
@@ -82,6 +89,10 @@ fn validate_tree<V>(t: &HotTree<V>) {
         leaf_count, t.count,
         "reachable leaf count must match HotTree::len"
     );
+    assert_eq!(
+        prefix_refs, t.prefix_refs,
+        "prefix reference counts must match reachable leaves"
+    );
 }
 
 #[derive(Clone, Debug)]
@@ -93,14 +104,23 @@ enum Op<V> {
 }
 
 fn key_strategy() -> impl Strategy<Value = Vec<u8>> + Clone {
-    // URLs and most string-like keys never contain 0x00 bytes. This avoids the
-    // current limitation where keys that differ only by trailing 0x00 bytes
-    // are not distinguishable at the bit level.
-    prop::collection::vec(1u8..=255, 0..=64)
+    // Any bytes, 0x00 included; `small_alphabet_key` makes keys that differ only by
+    // trailing 0x00 bytes common.
+    prop::collection::vec(any::<u8>(), 0..=64)
+}
+
+fn delimited_key_strategy() -> impl Strategy<Value = Vec<u8>> + Clone {
+    // Small alphabet rich in prefix/tail delimiters to exercise prefix and tail interning.
+    prop::collection::vec(prop::sample::select(b"ab:/\\".to_vec()), 0..=24)
 }
 
 fn ops_strategy_u64() -> impl Strategy<Value = Vec<Op<u64>>> {
-    let key = key_strategy();
+    ops_strategy_u64_with(key_strategy())
+}
+
+fn ops_strategy_u64_with(
+    key: impl Strategy<Value = Vec<u8>> + Clone,
+) -> impl Strategy<Value = Vec<Op<u64>>> {
     let op = prop_oneof![
         50 => (key.clone(), any::<u64>()).prop_map(|(k, v)| Op::Insert(k, v)),
         25 => key.clone().prop_map(Op::Remove),
@@ -121,6 +141,41 @@ fn ops_strategy_zst() -> impl Strategy<Value = Vec<Op<()>>> {
     prop::collection::vec(op, 0..=2000)
 }
 
+fn check_equivalence_u64(mut t: HotTree<u64>, ops: Vec<Op<u64>>) -> Result<(), TestCaseError> {
+    let mut m: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+
+    for op in ops {
+        match op {
+            Op::Insert(key, value) => {
+                let old_t = t.insert(&key, value);
+                let old_m = m.insert(key, value);
+                prop_assert_eq!(old_t, old_m);
+            }
+            Op::Remove(key) => {
+                let old_t = t.remove(&key);
+                let old_m = m.remove(key.as_slice());
+                prop_assert_eq!(old_t, old_m);
+            }
+            Op::Get(key) => {
+                let got_t = t.get(&key).copied();
+                let got_m = m.get(key.as_slice()).copied();
+                prop_assert_eq!(got_t, got_m);
+            }
+            Op::Compact => {
+                t.compact();
+            }
+        }
+
+        prop_assert_eq!(t.len(), m.len());
+    }
+
+    validate_tree(&t);
+    let got: Vec<(Vec<u8>, u64)> = t.iter().map(|(k, v)| (k, *v)).collect();
+    let expected: Vec<(Vec<u8>, u64)> = m.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    prop_assert_eq!(got, expected);
+    Ok(())
+}
+
 proptest! {
     #![proptest_config(ProptestConfig {
         cases: 256,
@@ -130,38 +185,41 @@ proptest! {
 
     #[test]
     fn prop_equivalence_u64(ops in ops_strategy_u64()) {
-        let mut t: HotTree<u64> = HotTree::new();
-        let mut m: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+        check_equivalence_u64(HotTree::new(), ops)?;
+    }
+
+    #[test]
+    fn prop_equivalence_u64_interned(ops in ops_strategy_u64_with(delimited_key_strategy())) {
+        check_equivalence_u64(HotTree::new().with_suffix_interning(true), ops)?;
+    }
+
+    #[test]
+    fn prop_simd_matches_scalar(ops in ops_strategy_u64()) {
+        let mut simd: HotTree<u64> = HotTree::new().with_simd(true);
+        let mut scalar: HotTree<u64> = HotTree::new().with_simd(false);
 
         for op in ops {
             match op {
                 Op::Insert(key, value) => {
-                    let old_t = t.insert(&key, value);
-                    let old_m = m.insert(key, value);
-                    prop_assert_eq!(old_t, old_m);
+                    prop_assert_eq!(simd.insert(&key, value), scalar.insert(&key, value));
                 }
                 Op::Remove(key) => {
-                    let old_t = t.remove(&key);
-                    let old_m = m.remove(key.as_slice());
-                    prop_assert_eq!(old_t, old_m);
+                    prop_assert_eq!(simd.remove(&key), scalar.remove(&key));
                 }
                 Op::Get(key) => {
-                    let got_t = t.get(&key).copied();
-                    let got_m = m.get(key.as_slice()).copied();
-                    prop_assert_eq!(got_t, got_m);
+                    prop_assert_eq!(simd.get(&key), scalar.get(&key));
                 }
                 Op::Compact => {
-                    t.compact();
+                    simd.compact();
+                    scalar.compact();
                 }
             }
-
-            prop_assert_eq!(t.len(), m.len());
         }
 
-        validate_tree(&t);
-        let got: Vec<(Vec<u8>, u64)> = t.iter().map(|(k, v)| (k, *v)).collect();
-        let expected: Vec<(Vec<u8>, u64)> = m.iter().map(|(k, v)| (k.clone(), *v)).collect();
-        prop_assert_eq!(got, expected);
+        validate_tree(&simd);
+        validate_tree(&scalar);
+        prop_assert_eq!(simd.structure_digest(), scalar.structure_digest());
+        prop_assert!(simd.iter().eq(scalar.iter()));
     }
 
     #[test]
@@ -201,6 +259,63 @@ proptest! {
     }
 }
 
+proptest! {
+    #[test]
+    fn prop_iter_sorted_strictly_ascending(
+        keys in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..=24), 0..=400),
+    ) {
+        fn collect_sorted<I: SortedIterator>(it: I) -> Vec<I::Item> {
+            it.collect()
+        }
+
+        let mut t: HotTree<u64> = HotTree::new();
+        for (i, k) in keys.iter().enumerate() {
+            let _ = t.try_insert(k, i as u64);
+        }
+        let got: Vec<Vec<u8>> = collect_sorted(t.iter_sorted()).into_iter().map(|(k, _)| k).collect();
+        prop_assert_eq!(got.len(), t.len());
+        prop_assert!(got.windows(2).all(|w| w[0] < w[1]), "{:?}", got);
+    }
+}
+
+fn small_alphabet_key() -> impl Strategy<Value = Vec<u8>> + Clone {
+    prop::collection::vec(prop::sample::select(vec![0u8, 1, b'a', b'b', 0xFF]), 0..=10)
+}
+
+proptest! {
+    #[test]
+    fn prop_seek_matches_btreemap(
+        keys in prop::collection::vec(small_alphabet_key(), 0..=300),
+        probes in prop::collection::vec(small_alphabet_key(), 1..=32),
+    ) {
+        let mut t: HotTree<u64> = HotTree::new();
+        let mut m: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+        for (i, k) in keys.into_iter().enumerate() {
+            t.insert(&k, i as u64);
+            m.insert(k, i as u64);
+        }
+
+        for p in probes {
+            let expected = m.range(p.clone()..).next().is_some_and(|(k, _)| k.starts_with(&p));
+            prop_assert_eq!(t.contains_prefix(&p), expected, "prefix {:?}", p);
+
+            let first = m.range(p.clone()..).next().filter(|(k, _)| k.starts_with(&p));
+            prop_assert_eq!(t.first_with_prefix(&p), first.map(|(k, v)| (k.clone(), v)));
+
+            let scanned: Vec<(Vec<u8>, &u64)> = t.prefix_scan(&p).collect();
+            let expected: Vec<(Vec<u8>, &u64)> = m
+                .range(p.clone()..)
+                .take_while(|(k, _)| k.starts_with(&p))
+                .map(|(k, v)| (k.clone(), v))
+                .collect();
+            prop_assert_eq!(scanned, expected);
+
+            let bound = LeafWalk::seek(&t, &p).next_leaf().map(|(off, _)| t.get_leaf_key(off));
+            prop_assert_eq!(bound.as_ref(), m.range(p.clone()..).next().map(|(k, _)| k));
+        }
+    }
+}
+
 fn for_each_permutation<T: Clone>(items: &[T], mut f: impl FnMut(Vec<T>)) {
     fn rec<T: Clone>(items: &[T], used: &mut [bool], out: &mut Vec<T>, f: &mut impl FnMut(Vec<T>)) {
         if out.len() == items.len() {
@@ -283,3 +398,230 @@ fn exhaustive_remove_order_small_set() {
         assert!(t.root.is_null());
     });
 }
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_prefix_scan_matches_sequential() {
+    let mut t: HotTree<u64> = HotTree::new();
+    for i in 0..100_000u64 {
+        t.insert(format!("https://host{}.example/{}/{i}", i % 7, i % 13), i);
+    }
+    for prefix in [
+        &b""[..],
+        b"https://host3",
+        b"https://host3.example/1",
+        b"https://host3.example/12/",
+        b"https://host3.example/12/3",
+        b"https://host9",
+        b"zzz",
+    ] {
+        let seq: Vec<(Vec<u8>, u64)> = t.prefix_scan(prefix).map(|(k, v)| (k, *v)).collect();
+        assert_eq!(t.par_prefix_scan(prefix), seq, "prefix {prefix:?}");
+    }
+    assert_eq!(t.par_prefix_scan(b"").len(), 100_000);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_build_sorted_matches_sequential() {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut m: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+    while m.len() < 50_000 {
+        let host = rng.gen_range(0..200);
+        let path: u32 = rng.gen();
+        let key = format!("https://host{host}.example/{path:08x}");
+        m.insert(key.into_bytes(), rng.gen());
+    }
+    let entries: Vec<(Vec<u8>, u64)> = m.iter().map(|(k, v)| (k.clone(), *v)).collect();
+
+    let seq: HotTree<u64> = HotTree::build_sorted(entries.clone()).unwrap();
+    // A fixed thread count makes the chunk plan, and so the stitched height, deterministic.
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .unwrap();
+    let mut par: HotTree<u64> = pool.install(|| HotTree::par_build_sorted(entries)).unwrap();
+    validate_tree(&par);
+    assert_eq!(par.len(), seq.len());
+    assert!(par.iter().eq(seq.iter()));
+    // The splits above the chunks share compound nodes rather than adding a level each.
+    assert!(par.height() <= seq.height() + 1);
+
+    // The stitched tree must keep working as an ordinary HotTree.
+    for i in 0..5_000u64 {
+        let key = format!("https://host{}.example/zz{i}", i % 300).into_bytes();
+        assert_eq!(par.insert(&key, i), m.insert(key, i));
+    }
+    let victims: Vec<Vec<u8>> = m.keys().step_by(3).cloned().collect();
+    for key in victims {
+        assert_eq!(par.remove(&key), m.remove(&key));
+    }
+    validate_tree(&par);
+    let got: Vec<(Vec<u8>, u64)> = par.iter().map(|(k, v)| (k, *v)).collect();
+    let expected: Vec<(Vec<u8>, u64)> = m.into_iter().collect();
+    assert_eq!(got, expected);
+}
+
+/// Keys whose first set bit is bit `i` form a fully skewed trie: each compound node
+/// absorbs at most 31 of them, so about 8000 keys reach the `u8` height cap.
+#[test]
+fn skewed_keys_stop_at_height_cap() {
+    let chain_key = |i: usize| {
+        let mut key = vec![0u8; i / 8 + 1];
+        key[i / 8] = 0x80 >> (i % 8);
+        key
+    };
+
+    let mut t: HotTree<u32> = HotTree::new();
+    let mut inserted = 0;
+    let err = (0..MAX_KEY_LEN * 8).find_map(|i| match t.try_insert(chain_key(i), i as u32) {
+        Ok(None) => {
+            inserted += 1;
+            None
+        }
+        Ok(Some(_)) => unreachable!(),
+        Err(e) => Some(e),
+    });
+    assert_eq!(err, Some(HotError::HeightLimit { max: u8::MAX }));
+    assert_eq!(t.ptr_height(t.root), u8::MAX);
+    validate_tree(&t);
+    for i in (0..inserted).step_by(13) {
+        assert_eq!(t.get(chain_key(i)), Some(&(i as u32)));
+    }
+
+    // Removing from the bottom of the chain recomputes heights all the way up.
+    for i in (0..inserted).rev().step_by(7) {
+        assert_eq!(t.remove(chain_key(i)), Some(i as u32));
+    }
+    validate_tree(&t);
+}
+
+/// Every limit is checked before `try_insert` mutates anything, so a failed insert must
+/// leave no trace: no leaf bytes, value slot, node, learned prefix or count change.
+#[test]
+fn failed_inserts_leave_tree_unchanged() {
+    fn footprint(t: &HotTree<u64>) -> [usize; 8] {
+        [
+            t.count,
+            t.leaves.len(),
+            t.values.len(),
+            t.nodes.data.len(),
+            t.nodes.free_bytes(),
+            t.prefix_offsets.len(),
+            t.generation as usize,
+            t.root.0 as usize,
+        ]
+    }
+
+    fn check_rejected(t: &mut HotTree<u64>, keys: &[Vec<u8>], expected: HotError) {
+        let before = footprint(t);
+        for key in keys {
+            assert_eq!(t.try_insert(key, 0), Err(expected.clone()));
+            assert_eq!(footprint(t), before);
+        }
+        validate_tree(t);
+    }
+
+    let key = |i: u64| format!("tenant{}/document/{i:06}", i % 5).into_bytes();
+    let fill = |t: &mut HotTree<u64>| {
+        let mut inserted = 0;
+        while t.try_insert(key(inserted), inserted).is_ok() {
+            inserted += 1;
+        }
+        inserted
+    };
+    // New keys under both known and never-seen prefixes.
+    let fresh: Vec<Vec<u8>> = (0..20)
+        .map(|i| format!("tenant{}/document/x{i}", i % 7).into_bytes())
+        .collect();
+
+    let mut t: HotTree<u64> = HotTree::new();
+    t.config.arena_limit = 16 * 1024;
+    let inserted = fill(&mut t);
+    check_rejected(&mut t, &fresh, HotError::ArenaFull { max: 16 * 1024 });
+    for i in 0..inserted {
+        assert_eq!(t.get(key(i)), Some(&i));
+    }
+
+    let mut t: HotTree<u64> = HotTree::new().with_memory_budget(64 * 1024);
+    let inserted = fill(&mut t);
+    check_rejected(&mut t, &fresh, HotError::MemoryBudget { max: 64 * 1024 });
+    for i in 0..inserted {
+        assert_eq!(t.get(key(i)), Some(&i));
+    }
+
+    // Interning allocates the tail sketch on the first insert, which the budget must cover.
+    let mut t: HotTree<u64> = HotTree::new()
+        .with_suffix_interning(true)
+        .with_memory_budget(3000);
+    assert_eq!(fill(&mut t), 0);
+    check_rejected(&mut t, &fresh, HotError::MemoryBudget { max: 3000 });
+    assert!(t.memory_usage() <= 3000);
+
+    let mut t: HotTree<u64> = HotTree::new()
+        .with_suffix_interning(true)
+        .with_memory_budget(64 * 1024);
+    let inserted = fill(&mut t);
+    assert!(inserted > 0);
+    assert!(t.memory_usage() <= 64 * 1024);
+    check_rejected(&mut t, &fresh, HotError::MemoryBudget { max: 64 * 1024 });
+    for i in 0..inserted {
+        assert_eq!(t.get(key(i)), Some(&i));
+    }
+
+    let mut t: HotTree<u64> = HotTree::new();
+    for i in 0..1000 {
+        t.insert(key(i), i);
+    }
+    let zero_padded: Vec<Vec<u8>> = (0..1000)
+        .step_by(97)
+        .map(|i| [key(i), vec![0, 0]].concat())
+        .collect();
+    for k in &zero_padded {
+        assert_eq!(t.try_insert(k, u64::MAX), Ok(None));
+    }
+    assert_eq!(t.get(key(0)), Some(&0));
+    assert_eq!(t.get(&zero_padded[0]), Some(&u64::MAX));
+    validate_tree(&t);
+}
+
+proptest! {
+    #[test]
+    fn prop_remove_many_matches_remove(
+        keys in prop::collection::vec(small_alphabet_key(), 0..=300),
+        doomed in prop::collection::vec(any::<prop::sample::Index>(), 0..=200),
+        absent in prop::collection::vec(small_alphabet_key(), 0..=20),
+    ) {
+        let mut t: HotTree<u64> = HotTree::new();
+        let mut m: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+        for (i, k) in keys.iter().enumerate() {
+            t.insert(k, i as u64);
+            m.insert(k.clone(), i as u64);
+        }
+
+        let mut batch: Vec<&[u8]> = Vec::new();
+        if !keys.is_empty() {
+            batch.extend(doomed.iter().map(|i| i.get(&keys).as_slice()));
+        }
+        batch.extend(absent.iter().map(Vec::as_slice));
+        let expected = batch.iter().filter(|k| m.remove(**k).is_some()).count();
+
+        prop_assert_eq!(t.remove_many(&batch), expected);
+        validate_tree(&t);
+        prop_assert!(t.iter().eq(m.iter().map(|(k, v)| (k.clone(), v))));
+        for k in keys.iter().chain(&absent) {
+            prop_assert_eq!(t.get(k), m.get(k));
+        }
+
+        // The rebuilt nodes still take inserts.
+        for (i, k) in absent.iter().enumerate() {
+            t.insert(k, i as u64);
+            m.insert(k.clone(), i as u64);
+        }
+        validate_tree(&t);
+        prop_assert!(t.iter().eq(m.iter().map(|(k, v)| (k.clone(), v))));
+    }
+}
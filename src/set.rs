@@ -0,0 +1,151 @@
+//! A set of byte strings over a zero-sized-value tree.
+
+use crate::{merge_join, EitherOrBoth, HotTree};
+
+/// A set of byte-string keys, stored as a [`HotTree<()>`] with no value slots.
+///
+/// Set operations walk both sets once in key order via [`merge_join`] and bulk-load the
+/// result, so they cost O(n + m) without lookups.
+///
+/// ```
+/// use hot_rs::HotSet;
+///
+/// let mut a = HotSet::new();
+/// assert!(a.insert("x"));
+/// assert!(!a.insert("x"));
+/// let b: HotSet = ["x", "y"].into_iter().collect();
+/// assert_eq!(a.union(&b).iter().collect::<Vec<_>>(), [b"x".to_vec(), b"y".to_vec()]);
+/// ```
+#[derive(Clone, Default)]
+pub struct HotSet {
+    tree: HotTree<()>,
+}
+
+impl HotSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Add `key`, returning whether it was newly added.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`HotTree::try_insert`] would return an error.
+    pub fn insert(&mut self, key: impl AsRef<[u8]>) -> bool {
+        self.tree.insert(key, ()).is_none()
+    }
+
+    pub fn contains(&self, key: impl AsRef<[u8]>) -> bool {
+        self.tree.contains_key(key)
+    }
+
+    /// Remove `key`, returning whether it was present.
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> bool {
+        self.tree.remove(key).is_some()
+    }
+
+    /// Iterate in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.tree.iter().map(|(key, _)| key)
+    }
+
+    /// Keys in `self`, `other` or both.
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |_| true)
+    }
+
+    /// Keys in both `self` and `other`.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |side| matches!(side, EitherOrBoth::Both(..)))
+    }
+
+    /// Keys in `self` but not `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |side| matches!(side, EitherOrBoth::Left(_)))
+    }
+
+    fn combine(&self, other: &Self, keep: impl Fn(&EitherOrBoth<&(), &()>) -> bool) -> Self {
+        let keys = merge_join(&self.tree, &other.tree)
+            .filter_map(|(key, side)| keep(&side).then_some((key, ())));
+        Self {
+            tree: HotTree::build_sorted(keys).expect("keys were accepted by the input sets"),
+        }
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.tree.memory_usage()
+    }
+}
+
+impl<K: AsRef<[u8]>> FromIterator<K> for HotSet {
+    fn from_iter<I: IntoIterator<Item = K>>(keys: I) -> Self {
+        let mut set = Self::new();
+        for key in keys {
+            set.insert(key);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeSet;
+
+    fn random_keys(rng: &mut impl Rng, n: usize) -> BTreeSet<Vec<u8>> {
+        (0..n)
+            .map(|_| format!("tag:{}", rng.gen_range(0..3000)).into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_hot_set_matches_btreeset() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2410);
+        let model_a = random_keys(&mut rng, 2000);
+        let model_b = random_keys(&mut rng, 2000);
+
+        let mut a = HotSet::new();
+        for key in &model_a {
+            assert!(a.insert(key));
+            assert!(!a.insert(key));
+        }
+        let b: HotSet = model_b.iter().collect();
+        assert_eq!(a.len(), model_a.len());
+        assert!(a.iter().eq(model_a.iter().cloned()));
+        for i in 0..3000 {
+            let key = format!("tag:{i}");
+            assert_eq!(a.contains(&key), model_a.contains(key.as_bytes()));
+        }
+
+        assert!(a.union(&b).iter().eq(model_a.union(&model_b).cloned()));
+        assert!(a
+            .intersection(&b)
+            .iter()
+            .eq(model_a.intersection(&model_b).cloned()));
+        assert!(a
+            .difference(&b)
+            .iter()
+            .eq(model_a.difference(&model_b).cloned()));
+        assert!(b
+            .difference(&a)
+            .iter()
+            .eq(model_b.difference(&model_a).cloned()));
+        assert!(a.intersection(&HotSet::new()).is_empty());
+
+        let first = model_a.iter().next().unwrap();
+        assert!(a.remove(first));
+        assert!(!a.remove(first));
+        assert!(!a.contains(first));
+    }
+}
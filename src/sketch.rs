@@ -0,0 +1,50 @@
+//! Frequency sketch deciding which key tails recur often enough to intern.
+
+/// Counters in the sketch, and sightings between halvings.
+const SLOTS: usize = 4096;
+/// Sightings of a tail after which it is interned.
+const INTERN_THRESHOLD: u8 = 8;
+
+/// Approximate sighting counts of key tails, in saturating counters indexed by tail hash.
+///
+/// Every counter is halved after each `SLOTS` sightings. A unique tail adds about one
+/// sighting per slot per period, so unique tails settle well below the threshold while a
+/// tail on even one key in a few hundred crosses it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TailSketch {
+    counts: Vec<u8>,
+    sightings: usize,
+}
+
+impl TailSketch {
+    /// Count a sighting of the tail with `hash`, returning whether it has now been seen
+    /// often enough to intern. Allocates the counters on first use.
+    pub(crate) fn record(&mut self, hash: u64) -> bool {
+        if self.counts.is_empty() {
+            self.counts = vec![0; SLOTS];
+        }
+        self.sightings += 1;
+        if self.sightings == SLOTS {
+            self.sightings = 0;
+            for count in &mut self.counts {
+                *count /= 2;
+            }
+        }
+        let count = &mut self.counts[((hash ^ (hash >> 32)) as usize) % SLOTS];
+        *count = count.saturating_add(1);
+        *count >= INTERN_THRESHOLD
+    }
+
+    /// Bytes the next [`TailSketch::record`] may allocate.
+    pub(crate) fn record_growth(&self) -> usize {
+        if self.counts.is_empty() {
+            SLOTS
+        } else {
+            0
+        }
+    }
+
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.counts.capacity()
+    }
+}
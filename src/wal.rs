@@ -0,0 +1,321 @@
+//! Trees that append every mutation to a write-ahead log.
+
+use std::io::{self, Read, Write};
+
+use crate::encoding::{read_bytes, write_bytes};
+use crate::{HotTree, ValueCodec};
+
+const OP_INSERT: u8 = 1;
+const OP_REMOVE: u8 = 2;
+
+/// A [`HotTree`] that writes each `insert` and `remove` to a log as it applies it, so
+/// [`HotTree::replay`] can rebuild the tree after a crash.
+///
+/// A record is an op byte (`1` insert, `2` remove), the key as an
+/// [`encoding`](crate::encoding) byte string and, for inserts, the value encoded by `C`
+/// as another byte string. Each record is passed to the writer in one `write_all`, and
+/// flushing is up to the writer: wrap a file in a `BufWriter` and flush or `sync_data`
+/// it at whatever interval the application needs.
+///
+/// Only inserts the tree accepts are logged, so every logged record replays. A failed
+/// write may leave part of a record in the log; the tree then refuses further writes,
+/// keeping the torn record last, where replay drops it.
+///
+/// ```
+/// use hot_rs::{HotTree, ValueCodec, WalHotTree};
+///
+/// struct U64Codec;
+/// impl ValueCodec<u64> for U64Codec {
+///     fn encode(&self, value: &u64) -> Vec<u8> {
+///         value.to_le_bytes().to_vec()
+///     }
+///     fn decode(&self, bytes: &[u8]) -> u64 {
+///         u64::from_le_bytes(bytes.try_into().unwrap())
+///     }
+/// }
+///
+/// let mut wal = WalHotTree::new(Vec::new(), U64Codec);
+/// wal.insert("a", 1).unwrap();
+/// wal.insert("b", 2).unwrap();
+/// wal.remove("a").unwrap();
+/// let (_, log) = wal.into_inner();
+///
+/// let recovered = HotTree::replay(log.as_slice(), &U64Codec).unwrap();
+/// assert_eq!(recovered.get("b"), Some(&2));
+/// assert_eq!(recovered.len(), 1);
+/// ```
+pub struct WalHotTree<V, C, W> {
+    tree: HotTree<V>,
+    writer: W,
+    codec: C,
+    record: Vec<u8>,
+    /// Set when a write fails, possibly after appending part of a record.
+    poisoned: bool,
+}
+
+impl<V, C: ValueCodec<V>, W: Write> WalHotTree<V, C, W> {
+    /// Start an empty tree logging to `writer`.
+    pub fn new(writer: W, codec: C) -> Self {
+        Self {
+            tree: HotTree::new(),
+            writer,
+            codec,
+            record: Vec::new(),
+            poisoned: false,
+        }
+    }
+
+    /// Apply and log an insert, returning the previous value.
+    ///
+    /// An insert [`HotTree::try_insert`] rejects is not logged, and its
+    /// [`HotError`](crate::HotError) is returned inside an [`io::ErrorKind::Other`] error.
+    /// If the log write fails, the insert is undone and the tree is
+    /// [poisoned](WalHotTree::is_poisoned).
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: V) -> io::Result<Option<V>> {
+        let key = key.as_ref();
+        self.check_poisoned()?;
+        self.record.clear();
+        self.record.push(OP_INSERT);
+        write_bytes(key, &mut self.record);
+        write_bytes(&self.codec.encode(&value), &mut self.record);
+        let old = self.tree.try_insert(key, value).map_err(io::Error::other)?;
+        if let Err(e) = self.append_record() {
+            match old {
+                Some(old) => {
+                    self.tree.insert(key, old);
+                }
+                None => {
+                    self.tree.remove(key);
+                }
+            }
+            return Err(e);
+        }
+        Ok(old)
+    }
+
+    /// Log and apply a removal, returning the removed value.
+    ///
+    /// Absent keys are logged too. If the log write fails, the tree is left unchanged and
+    /// [poisoned](WalHotTree::is_poisoned).
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> io::Result<Option<V>> {
+        let key = key.as_ref();
+        self.check_poisoned()?;
+        self.record.clear();
+        self.record.push(OP_REMOVE);
+        write_bytes(key, &mut self.record);
+        self.append_record()?;
+        Ok(self.tree.remove(key))
+    }
+
+    /// Whether a failed write or flush has stopped further logging. The log may then end
+    /// in a torn record, which [`HotTree::replay`] drops, and `insert`, `remove` and
+    /// `flush` return errors; recover by replaying the log.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    fn check_poisoned(&self) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other(
+                "WAL writer failed earlier and may hold a torn record",
+            ));
+        }
+        Ok(())
+    }
+
+    fn append_record(&mut self) -> io::Result<()> {
+        let result = self.writer.write_all(&self.record);
+        self.poisoned = result.is_err();
+        result
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// The tree, for the read methods of [`HotTree`].
+    pub fn tree(&self) -> &HotTree<V> {
+        &self.tree
+    }
+
+    /// Flush the log writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.check_poisoned()?;
+        let result = self.writer.flush();
+        self.poisoned = result.is_err();
+        result
+    }
+
+    /// The tree and the log writer, which is not flushed.
+    pub fn into_inner(self) -> (HotTree<V>, W) {
+        (self.tree, self.writer)
+    }
+}
+
+impl<V> HotTree<V> {
+    /// Rebuild a tree by applying every record of a [`WalHotTree`] log in order.
+    ///
+    /// A record cut short at the end of the log, as left by a crash or failed write while
+    /// appending, is ignored. An unknown op byte, or an insert [`HotTree::try_insert`]
+    /// rejects, is an [`io::ErrorKind::InvalidData`] error.
+    pub fn replay<C: ValueCodec<V>>(mut reader: impl Read, codec: &C) -> io::Result<Self> {
+        let mut log = Vec::new();
+        reader.read_to_end(&mut log)?;
+        let mut tree = Self::new();
+        let mut rest = log.as_slice();
+        while let Some((&op, body)) = rest.split_first() {
+            let Some((key, key_len)) = read_bytes(body) else {
+                break;
+            };
+            let body = &body[key_len..];
+            rest = match op {
+                OP_INSERT => {
+                    let Some((value, value_len)) = read_bytes(body) else {
+                        break;
+                    };
+                    tree.try_insert(key, codec.decode(value))
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                    &body[value_len..]
+                }
+                OP_REMOVE => {
+                    tree.remove(key);
+                    body
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown WAL op {op}"),
+                    ))
+                }
+            };
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    struct U64Codec;
+
+    impl ValueCodec<u64> for U64Codec {
+        fn encode(&self, value: &u64) -> Vec<u8> {
+            value.to_le_bytes().to_vec()
+        }
+
+        fn decode(&self, bytes: &[u8]) -> u64 {
+            u64::from_le_bytes(bytes.try_into().unwrap())
+        }
+    }
+
+    #[test]
+    fn test_replay_matches_logged_tree() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2427);
+        let mut wal = WalHotTree::new(Vec::new(), U64Codec);
+        for i in 0..20_000u64 {
+            let key = format!("session:{}", rng.gen_range(0..5000));
+            if rng.gen_bool(0.3) {
+                wal.remove(&key).unwrap();
+            } else {
+                wal.insert(&key, i).unwrap();
+            }
+        }
+        wal.flush().unwrap();
+        let (tree, log) = wal.into_inner();
+
+        let replayed = HotTree::replay(log.as_slice(), &U64Codec).unwrap();
+        assert_eq!(replayed.len(), tree.len());
+        assert!(replayed.iter().eq(tree.iter()));
+
+        // A torn final record is dropped; everything before it survives.
+        let mut wal = WalHotTree::new(Vec::new(), U64Codec);
+        wal.insert("kept", 1).unwrap();
+        wal.insert("torn", 2).unwrap();
+        let (_, log) = wal.into_inner();
+        let torn = HotTree::replay(&log[..log.len() - 3], &U64Codec).unwrap();
+        assert_eq!(torn.get("kept"), Some(&1));
+        assert_eq!(torn.get("torn"), None);
+
+        let err = HotTree::<u64>::replay(&[9u8, 0][..], &U64Codec).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// Accepts `budget` more bytes, then fails every write.
+    struct FailingWriter {
+        log: Vec<u8>,
+        budget: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.budget == 0 {
+                return Err(io::Error::other("disk full"));
+            }
+            let n = buf.len().min(self.budget);
+            self.log.extend_from_slice(&buf[..n]);
+            self.budget -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failed_writes_keep_log_replayable() {
+        let writer = FailingWriter {
+            log: Vec::new(),
+            budget: 40,
+        };
+        let mut wal = WalHotTree::new(writer, U64Codec);
+        wal.insert("", 1).unwrap();
+        wal.insert("\x00", 2).unwrap();
+
+        // Rejected inserts are not logged.
+        let long = vec![b'k'; crate::MAX_KEY_LEN + 1];
+        let err = wal.insert(&long, 3).unwrap_err();
+        let cause = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<crate::HotError>());
+        assert!(
+            matches!(cause, Some(crate::HotError::KeyTooLong { .. })),
+            "{err}"
+        );
+        assert!(!wal.is_poisoned());
+
+        // A write torn partway is undone in the tree and stops further logging.
+        wal.insert("kept", 4).unwrap();
+        assert!(wal.insert("kept", 5).is_err());
+        assert!(wal.is_poisoned());
+        assert_eq!(wal.get("kept"), Some(&4));
+        assert!(wal.insert("new", 6).is_err());
+        assert!(wal.remove("kept").is_err());
+        assert_eq!(wal.len(), 3);
+
+        let (tree, writer) = wal.into_inner();
+        let replayed = HotTree::replay(writer.log.as_slice(), &U64Codec).unwrap();
+        assert!(replayed.iter().eq(tree.iter()));
+        assert_eq!(replayed.get(""), Some(&1));
+        assert_eq!(replayed.get("\x00"), Some(&2));
+
+        // A logged insert the tree rejects is corrupt data, not a panic.
+        let mut log = vec![OP_INSERT];
+        write_bytes(&long, &mut log);
+        write_bytes(&U64Codec.encode(&3), &mut log);
+        let err = HotTree::replay(log.as_slice(), &U64Codec).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}